@@ -0,0 +1,117 @@
+//! Benchmark, comparing the throughput of sbcut with coreutils cut
+//!
+//! A CSV file of about 100 MB is generated in the target directory,
+//! and each command is run a few times on it. The best time of each command is reported.
+//! ```sh
+//! cargo bench --bench throughput
+//! ```
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+static LINE_COUNT: usize = 1_000_000;
+static RUN_COUNT: usize = 5;
+
+fn main() {
+    let input = generate_input();
+    let size = fs::metadata(&input).unwrap().len();
+    let sbcut = env!("CARGO_BIN_EXE_sbcut");
+
+    // The `cut` ranges are 1-based and inclusive, while the `sbcut` ones are Python-style
+    let cases: [(&str, &[&str], &[&str]); 3] = [
+        (
+            "fields",
+            &["-d", ",", "-f", "0,2"],
+            &["-d", ",", "-f", "1,3"],
+        ),
+        (
+            "field range",
+            &["-d", ",", "-f", "1:5"],
+            &["-d", ",", "-f", "2-5"],
+        ),
+        ("bytes", &["-b", ":20"], &["-b", "1-20"]),
+    ];
+
+    println!("input: {} lines, {:.1} MB", LINE_COUNT, megabytes(size));
+    for (name, sbcut_args, cut_args) in cases {
+        let sbcut_time = best_time(sbcut, sbcut_args, &input);
+        print_result(name, "sbcut", size, sbcut_time);
+
+        match best_time_if_available("cut", cut_args, &input) {
+            Some(cut_time) => print_result(name, "cut", size, cut_time),
+            None => println!("{:<12} {:<6} not available", name, "cut"),
+        }
+    }
+}
+
+fn generate_input() -> PathBuf {
+    //! Generate the input once, and reuse it in the next runs
+    let directory =
+        PathBuf::from(env::var("CARGO_TARGET_TMPDIR").unwrap_or(String::from("target")));
+    let path = directory.join("throughput.csv");
+    if path.exists() {
+        return path;
+    }
+
+    fs::create_dir_all(&directory).unwrap();
+    let mut output = BufWriter::new(File::create(&path).unwrap());
+    for index in 0..LINE_COUNT {
+        writeln!(
+            output,
+            "{},user{},{}@example.com,{},{},Lorem ipsum dolor sit amet consectetur,{}",
+            index,
+            index % 1000,
+            index,
+            index % 97,
+            index * 7 % 10_000,
+            index % 2 == 0
+        )
+        .unwrap();
+    }
+    output.flush().unwrap();
+
+    path
+}
+
+fn best_time(command: &str, args: &[&str], input: &PathBuf) -> Duration {
+    best_time_if_available(command, args, input).unwrap_or_else(|| panic!("Can't run {}", command))
+}
+
+fn best_time_if_available(command: &str, args: &[&str], input: &PathBuf) -> Option<Duration> {
+    let mut best: Option<Duration> = None;
+
+    for _ in 0..RUN_COUNT {
+        let start = Instant::now();
+        let status = Command::new(command)
+            .args(args)
+            .arg(input)
+            .stdout(Stdio::null())
+            .status()
+            .ok()?;
+        let elapsed = start.elapsed();
+
+        if !status.success() {
+            return None;
+        }
+        best = Some(best.map_or(elapsed, |best| best.min(elapsed)));
+    }
+
+    best
+}
+
+fn print_result(name: &str, command: &str, size: u64, time: Duration) {
+    println!(
+        "{:<12} {:<6} {:>8.1} ms {:>8.1} MB/s",
+        name,
+        command,
+        time.as_secs_f64() * 1000.0,
+        megabytes(size) / time.as_secs_f64()
+    );
+}
+
+fn megabytes(size: u64) -> f64 {
+    size as f64 / 1_000_000.0
+}
@@ -0,0 +1,5 @@
+# account, name, balance, currency
+10
+20
+8
+*
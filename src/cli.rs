@@ -1,27 +1,190 @@
 use clap::{arg, Command};
 use clap::{ArgAction, ArgGroup};
 
+use crate::config::{find_preset, preset_to_args};
+
 pub fn build_cli() -> Command {
     Command::new("sbcut")
         .version("0.1")
         .about("Slightly better cut")
-        .arg(arg!(-b --bytes <LIST>).allow_negative_numbers(true))
-        .arg(arg!(-c --characters <LIST>).allow_negative_numbers(true))
+        .after_help("Use @NAME as the first argument to apply the preset NAME from the config file")
+        .args_override_self(true)
+        .arg(arg!(-b --bytes <LIST>).allow_hyphen_values(true))
+        .arg(arg!(-c --characters <LIST>).allow_hyphen_values(true))
         .arg(
-            arg!(-d --delimiter <DELIM>)
-                .requires("fields")
+            arg!(-d --delimiter <DELIM> "Use DELIM as the field delimiter, or detect it from the first lines with 'auto'. Escapes such as '\\t', '\\x1f' and '\\u{2502}' are supported")
+                .requires("delimited")
                 .default_value("\t"),
         )
-        .arg(arg!(-f --fields <LIST>).allow_negative_numbers(true))
+        .arg(arg!(-f --fields <LIST>).allow_hyphen_values(true))
+        .arg(
+            arg!(--widths <WIDTHS> "Split each line into fixed-width fields of WIDTHS characters, e.g. '10,20,8,*'")
+                .conflicts_with_all(["bytes", "characters", "delimiter", "json"]),
+        )
+        .arg(
+            arg!(--"col-spec" <FILE> "Read the fixed-width field WIDTHS from FILE")
+                .conflicts_with_all(["bytes", "characters", "delimiter", "json", "widths"]),
+        )
         .arg(arg!(--complement).action(ArgAction::SetTrue))
         .arg(arg!(-s --only_delimited).action(ArgAction::SetTrue))
         .arg(arg!(--always_show_no_delimited_lines).action(ArgAction::SetTrue)) // If a line is not delimited, always show it in full (cut compatibility)
         .arg(arg!(--output_delimiter <DELIM>))
-        .arg(arg!(-z --zero_terminated).action(ArgAction::SetTrue))
+        .arg(
+            arg!(--"literal-delimiter" "Use the delimiters as they are, without replacing escape sequences such as '\\t'")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            arg!(-z --zero_terminated "Records end with NUL instead of a line break, in the input and the output")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            arg!(--"record-separator" <SEP> "Records in the input end with SEP instead of a line break. An empty SEP separates records by blank lines")
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            arg!(--crlf <POLICY> "Remove the carriage return from the end of the records, and add it back to the output records that had one ('keep') or not ('strip'). 'auto' keeps it if the first record has one")
+                .value_parser(["strip", "keep", "auto"])
+                .default_value("auto"),
+        )
+        .arg(
+            arg!(--"output-record-separator" <SEP> "End each output record with SEP instead of a line break")
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            arg!(--where <CONDITION> "Only output lines matching the condition, e.g. '2>=30 and -1~^Software'")
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            arg!(-H --header "Treat the first line as a header, which is always shown")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            arg!(--explain [MODE] "Show how the LIST is applied to the first line (or to all lines) instead of cutting")
+                .value_parser(["first", "all"])
+                .require_equals(true)
+                .default_missing_value("first"),
+        )
+        .arg(
+            arg!(--json "Parse each line as JSON, the LIST of -f is made of keys and dotted paths")
+                .action(ArgAction::SetTrue)
+                .requires("fields")
+                .conflicts_with_all(["where", "explain"]),
+        )
+        .arg(
+            arg!(--"output-format" <FORMAT> "Format of the output")
+                .value_parser(["plain", "jsonl", "csv", "tsv", "table", "markdown", "html"])
+                .default_value("plain"),
+        )
+        .arg(
+            arg!(--"table-sample" <N> "Calculate the table column widths from the first N lines, instead of all of them (with --output-format=table)")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            arg!(--"table-border" "Draw borders around the table (with --output-format=table)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            arg!(--format <TEMPLATE> "Output the TEMPLATE for each line, e.g. '{1} <{3}>', instead of the selected fields")
+                .allow_hyphen_values(true)
+                .conflicts_with("explain"),
+        )
+        .arg(
+            arg!(--decompress <MODE> "Decompress gzip, zstd, bzip2 and xz inputs, detected by their magic bytes ('auto'), or read the input as it is ('none')")
+                .value_parser(["auto", "none"])
+                .default_value("auto"),
+        )
+        .arg(arg!(--encoding <NAME> "Decode the input from the encoding NAME, e.g. 'latin1', 'windows-1252' or 'utf-16le', instead of UTF-8"))
+        .arg(
+            arg!(--"invalid-utf8" <MODE> "On invalid UTF-8, stop ('error'), replace it with U+FFFD ('replace'), skip the line ('skip-line') or keep the invalid bytes as they are and cut the line byte-wise ('bytes')")
+                .value_parser(["replace", "skip-line", "error", "bytes"])
+                .default_value("error"),
+        )
+        .arg(arg!(--"output-encoding" <NAME> "Encode the output with the encoding NAME instead of UTF-8"))
+        .arg(
+            arg!(-j --threads <N> "Cut the lines with N threads, in chunks written in the original order (only for the plain, csv and tsv output formats)")
+                .value_parser(clap::value_parser!(u16).range(1..))
+                .default_value("1"),
+        )
+        .arg(
+            arg!(-F --follow "Keep reading FILE as it grows, and reopen it when it is rotated, like 'tail -F' (not with --output-format=html)")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["threads", "explain"]),
+        )
+        .arg(
+            arg!(--"line-buffered" "Flush the output at the end of each record, instead of when the buffer is full, and read standard input as it comes")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            arg!(-v --verbose "Report the detected settings, such as the delimiter, on stderr")
+                .action(ArgAction::SetTrue),
+        )
         .arg(arg!([FILE]).default_value("-"))
         .group(
             ArgGroup::new("action")
                 .required(true)
-                .args(["bytes", "characters", "fields"]),
+                .args(["bytes", "characters", "fields", "format"]),
+        )
+        .group(
+            ArgGroup::new("delimited")
+                .multiple(true)
+                .args(["fields", "where", "format"]),
         )
 }
+
+pub fn expand_preset(args: Vec<String>) -> Result<Vec<String>, String> {
+    //! Replace a `@NAME` first argument with the options from the preset `NAME`.
+    //! Later arguments starting with `@`, such as `-d @` or a file named `@data`, are kept as they are.
+    //!
+    //! The preset options are put before the explicit ones, so that the explicit ones take precedence.
+    //! If a `-b`, `-c` or `-f` is given explicitly, the preset's LIST is ignored.
+    let preset_position = 1;
+    if !args
+        .get(preset_position)
+        .is_some_and(|arg| arg.starts_with('@'))
+    {
+        return Ok(args);
+    }
+
+    let preset = find_preset(&args[preset_position][1..])?;
+    let cli = build_cli();
+
+    for key in preset.keys() {
+        if !cli
+            .get_arguments()
+            .any(|arg| arg.get_long() == Some(key.as_str()))
+        {
+            return Err(format!("Unknown option \"{}\" in preset", key));
+        }
+    }
+
+    let explicit_args: Vec<String> = args
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != 0 && *index != preset_position)
+        .map(|(_, arg)| arg.clone())
+        .collect();
+
+    let has_explicit_action = explicit_args.iter().any(|arg| is_action_argument(arg));
+
+    let preset_args = preset_to_args(&preset)?
+        .into_iter()
+        .filter(|arg| !(has_explicit_action && is_action_argument(arg)));
+
+    Ok(std::iter::once(args[0].clone())
+        .chain(preset_args)
+        .chain(explicit_args)
+        .collect())
+}
+
+fn is_action_argument(arg: &str) -> bool {
+    //! Check if the argument is one of `-b`, `-c` or `-f`
+    if let Some(long) = arg.strip_prefix("--") {
+        ["bytes", "characters", "fields"]
+            .iter()
+            .any(|action| long == *action || long.starts_with(&format!("{}=", action)))
+    } else if let Some(short) = arg.strip_prefix('-') {
+        short.starts_with(['b', 'c', 'f'])
+    } else {
+        false
+    }
+}
@@ -0,0 +1,133 @@
+//! Module, containing the configuration file handling
+//!
+//! Named presets are read from `$XDG_CONFIG_HOME/sbcut/config.toml` (or `~/.config/sbcut/config.toml`)
+//! and from `.sbcut.toml` in the current directory. If both define a preset with the same name,
+//! the one from the current directory is used.
+//!
+//! Each preset is a table under `presets`, whose keys are the long option names:
+//! ```toml
+//! [presets.access-ids]
+//! delimiter = " "
+//! fields = "0,2:4"
+//! complement = true
+//! ```
+//! The preset above is used with `sbcut @access-ids file.log`
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use toml::{Table, Value};
+
+pub fn find_preset(name: &str) -> Result<Table, String> {
+    //! Find the preset with the given name in the configuration files.
+    let mut preset = None;
+
+    for path in config_paths() {
+        if !path.is_file() {
+            continue;
+        }
+
+        let config = read_config(&path)?;
+        if let Some(found) = config.get("presets").and_then(|presets| presets.get(name)) {
+            match found {
+                Value::Table(table) => preset = Some(table.clone()),
+                _ => {
+                    return Err(format!(
+                        "Preset \"{}\" in {} is not a table",
+                        name,
+                        path.display()
+                    ))
+                }
+            }
+        }
+    }
+
+    preset.ok_or(format!("Unknown preset \"{}\"", name))
+}
+
+pub fn preset_to_args(preset: &Table) -> Result<Vec<String>, String> {
+    //! Convert the preset into command line arguments.
+    //!
+    //! Strings and numbers become `--key=value`, `true` becomes `--key` and `false` is skipped.
+    let mut args = vec![];
+
+    for (key, value) in preset {
+        match value {
+            Value::String(value) => args.push(format!("--{}={}", key, value)),
+            Value::Integer(value) => args.push(format!("--{}={}", key, value)),
+            Value::Boolean(true) => args.push(format!("--{}", key)),
+            Value::Boolean(false) => {}
+            _ => return Err(format!("Invalid value for \"{}\" in preset", key)),
+        }
+    }
+
+    Ok(args)
+}
+
+fn config_paths() -> Vec<PathBuf> {
+    //! Return the configuration files, from the lowest to the highest priority
+    let mut paths = vec![];
+
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Some(config_home) if !config_home.is_empty() => Some(PathBuf::from(config_home)),
+        _ => env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")),
+    };
+
+    if let Some(config_home) = config_home {
+        paths.push(config_home.join("sbcut").join("config.toml"));
+    }
+    paths.push(PathBuf::from(".sbcut.toml"));
+
+    paths
+}
+
+fn read_config(path: &PathBuf) -> Result<Table, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => return Err(format!("Can't read config file {}", path.display())),
+    };
+
+    match content.parse::<Table>() {
+        Ok(config) => Ok(config),
+        Err(error) => Err(format!(
+            "Invalid config file {}: {}",
+            path.display(),
+            error.message()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_preset_to_args {
+    use super::preset_to_args;
+    use toml::Table;
+
+    #[test]
+    fn test_01_strings_and_flags() {
+        let preset: Table =
+            "delimiter = \",\"\nfields = \"0,2:4\"\ncomplement = true\nonly_delimited = false"
+                .parse()
+                .unwrap();
+
+        let args = preset_to_args(&preset).unwrap();
+
+        assert_eq!(
+            vec!["--complement", "--delimiter=,", "--fields=0,2:4"],
+            args
+        );
+    }
+
+    #[test]
+    fn test_02_integer() {
+        let preset: Table = "fields = 3".parse().unwrap();
+
+        assert_eq!(vec!["--fields=3"], preset_to_args(&preset).unwrap());
+    }
+
+    #[test]
+    fn test_03_invalid_value() {
+        let preset: Table = "fields = [1, 2]".parse().unwrap();
+
+        assert!(preset_to_args(&preset).is_err());
+    }
+}
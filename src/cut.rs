@@ -1,50 +1,195 @@
 /// Module containing the three cut methods - via a delimiter, via bytes and via characters.
-/// Each cut function returns the selected items from the line.
+/// Each cut function returns the selected items from the line, borrowed from it.
 ///
 /// `cut_line_with_delimiter` - cut a line based on a range and a delimiter
 ///
+/// `cut_line_with_splitter` - cut a line based on a range over the fields from a `FieldSplitter`
+///
 /// `cut_line_with_character` - cut a line based on a range over the characters
 ///
 /// `cut_line_with_bytes` - cut a line based on a range over the bytes.
-/// Treat each byte as UTF-8. If byte is not utf-8 encoded, print it with '0x' as prefix
+/// Treat each byte as UTF-8. If byte is not utf-8 encoded, print it with '0x' as prefix.
+/// The bytes of a record with invalid bytes kept by `--invalid-utf8=bytes` are split with `split_bytes` instead
+use crate::encoding::raw_byte;
 use crate::range_parser::Range;
-use std::collections::HashSet;
+use crate::splitter::{split_on_delimiter, FieldSplitter};
+use std::sync::OnceLock;
 
-pub fn cut_line_with_delimiter(
-    line: &str,
+// The non-ASCII bytes written in hexadecimal, created once so the cut bytes can be borrowed
+static HEX_BYTES: OnceLock<Vec<String>> = OnceLock::new();
+
+pub fn cut_line_with_delimiter<'a>(
+    line: &'a str,
     range: Range,
-    delimiter: String,
+    delimiter: &str,
     is_showing_complement: bool,
-) -> Vec<String> {
+) -> Vec<&'a str> {
     //! Cut the line with a given delimiter, and return the selected range.
-    let items: Vec<String> = line.split(&delimiter).map(String::from).collect();
+    let limit = item_limit(&[range], is_showing_complement);
+    let items = split_on_delimiter(line, delimiter, limit);
     let n = items.len() as i32;
 
-    cut_line(items, range, n, is_showing_complement)
+    cut_items(&items, range, n, is_showing_complement)
 }
 
-pub fn cut_line_with_bytes(line: &str, range: Range, is_showing_complement: bool) -> Vec<String> {
+pub fn cut_line_with_splitter<'a>(
+    line: &'a str,
+    range: Range,
+    splitter: &FieldSplitter,
+    is_showing_complement: bool,
+) -> Vec<&'a str> {
+    //! Cut the line into fields with the splitter, and return the selected range.
+    if let FieldSplitter::Delimiter(delimiter) = splitter {
+        return cut_line_with_delimiter(line, range, delimiter, is_showing_complement);
+    }
+
+    let limit = item_limit(&[range], is_showing_complement);
+    let items = splitter.split_first(line, limit);
+    let n = items.len() as i32;
+
+    cut_items(&items, range, n, is_showing_complement)
+}
+
+pub fn cut_line_with_bytes(line: &str, range: Range, is_showing_complement: bool) -> Vec<&str> {
     //! Cut the line and return the selected range of bytes.
-    let items: Vec<String> = line.bytes().map(handle_bytes).collect();
+    //! The bytes are selected by their indexes, without splitting the line.
     let n = line.len() as i32;
 
-    cut_line(items, range, n, is_showing_complement)
+    select_bytes(line, &resolve_indexes(range, n, is_showing_complement))
 }
 
 pub fn cut_line_with_characters(
     line: &str,
     range: Range,
     is_showing_complement: bool,
-) -> Vec<String> {
+) -> Vec<&str> {
     //! Cut the line and return the selected range of characters.
-    let items: Vec<String> = line.chars().map(String::from).collect();
+    let limit = item_limit(&[range], is_showing_complement);
+    let items = split_characters(line, limit);
     let n = line.len() as i32;
 
-    cut_line(items, range, n, is_showing_complement)
+    cut_items(&items, range, n, is_showing_complement)
 }
 
-fn cut_line(items: Vec<String>, range: Range, n: i32, is_showing_complement: bool) -> Vec<String> {
+pub fn cut_items<'a>(
+    items: &[&'a str],
+    range: Range,
+    n: i32,
+    is_showing_complement: bool,
+) -> Vec<&'a str> {
     //! Return the corresponding items to the range from the group.
+    select_items(items, &resolve_indexes(range, n, is_showing_complement))
+}
+
+pub fn select_items<'a>(items: &[&'a str], indexes: &[usize]) -> Vec<&'a str> {
+    //! Return the items at the indexes, skipping the indexes out of bounds
+    indexes
+        .iter()
+        .filter_map(|index| items.get(*index))
+        .copied()
+        .collect()
+}
+
+pub fn select_bytes<'a>(line: &'a str, indexes: &[usize]) -> Vec<&'a str> {
+    //! Return the bytes of the line at the indexes, skipping the indexes out of bounds
+    indexes
+        .iter()
+        .filter(|index| **index < line.len())
+        .map(|index| handle_byte(line, *index))
+        .collect()
+}
+
+pub fn split_bytes(line: &str) -> Vec<&str> {
+    //! Split a line that had invalid bytes into its bytes, written like `select_bytes` does.
+    //! The stand-in character of an invalid byte is one byte (see the `encoding` module).
+    let mut bytes = vec![];
+    for (index, character) in line.char_indices() {
+        let length = character.len_utf8();
+        match raw_byte(character) {
+            Some(_) => bytes.push(&line[index..index + length]),
+            None => bytes.extend((index..index + length).map(|index| handle_byte(line, index))),
+        }
+    }
+    bytes
+}
+
+pub fn split_characters(line: &str, limit: usize) -> Vec<&str> {
+    //! Split the first `limit` characters of the line
+    line.char_indices()
+        .take(limit)
+        .map(|(index, character)| &line[index..index + character.len_utf8()])
+        .collect()
+}
+
+pub fn item_limit(ranges: &[Range], is_showing_complement: bool) -> usize {
+    //! Return how many items of the line are needed to cut the ranges.
+    //!
+    //! Without negative indexes, only the items before the highest end are selected,
+    //! and one more item tells that the line is long enough for that end.
+    //! With negative indexes or the complement, all items are needed and `usize::MAX` is returned.
+    let mut limit = 0;
+    for range in ranges {
+        let (start, end, _) = range.to_tuple();
+        if is_showing_complement || start < 0 || end < 0 {
+            return usize::MAX;
+        }
+        limit = limit.max(end as usize + 1);
+    }
+
+    limit
+}
+
+pub fn resolve_indexes(range: Range, n: i32, is_showing_complement: bool) -> Vec<usize> {
+    //! Return the indexes of the items selected by the range, in the order they are output.
+    resolve_selection(&[range], n, is_showing_complement)
+}
+
+pub fn resolve_selection(ranges: &[Range], n: i32, is_showing_complement: bool) -> Vec<usize> {
+    //! Return the indexes of the items selected by all ranges, in the order they are output.
+    //!
+    //! Without the complement, the indexes of each range are output one range after another.
+    //! With it, the items selected by any range are marked in a bitmap of the `n` items,
+    //! and the unmarked ones are output in ascending order, or descending if all ranges step backwards.
+    //! Ranges which select nothing are ignored, and if all of them are, nothing is output.
+    if !is_showing_complement {
+        return ranges
+            .iter()
+            .flat_map(|range| range_indexes(*range, n))
+            .collect();
+    }
+
+    let mut is_selected = vec![false; n.max(0) as usize];
+    let mut has_valid_range = false;
+    for range in ranges {
+        let (start, end, step) = range.to_tuple();
+        if step == 0 {
+            continue;
+        }
+        if let Some((start, end)) = calculate_bounds(start, n, end) {
+            has_valid_range = true;
+            for index in (start..end.min(is_selected.len())).step_by(step.unsigned_abs() as usize) {
+                is_selected[index] = true;
+            }
+        }
+    }
+
+    if !has_valid_range {
+        return vec![];
+    }
+
+    let mut result: Vec<usize> = (0..is_selected.len())
+        .filter(|index| !is_selected[*index])
+        .collect();
+
+    if ranges.iter().all(|range| range.to_tuple().2 < 0) {
+        result.reverse();
+    }
+
+    result
+}
+
+fn range_indexes(range: Range, n: i32) -> Vec<usize> {
+    //! Return the indexes of the items selected by a single range, or none if it is out of bounds
     let (start, end, step) = range.to_tuple();
 
     if step == 0 {
@@ -52,33 +197,21 @@ fn cut_line(items: Vec<String>, range: Range, n: i32, is_showing_complement: boo
     }
 
     // TODO - Ugly hack
-    if !is_showing_complement {
-        let is_start_within_bounds = -n <= start && start < n;
-        //TODO - Add test for getting the last field from line
-        let is_end_within_bounds = -n <= end && end != 0 && end <= n;
+    let is_start_within_bounds = -n <= start && start < n;
+    //TODO - Add test for getting the last field from line
+    let is_end_within_bounds = -n <= end && end != 0 && end <= n;
 
-        if !(is_start_within_bounds && is_end_within_bounds) {
-            return vec![];
-        }
+    if !(is_start_within_bounds && is_end_within_bounds) {
+        return vec![];
     }
 
-    let indexes_to_get = match calculate_indexes_to_get(start, n, end, step) {
-        Some(value) => value,
+    let (start, end) = match calculate_bounds(start, n, end) {
+        Some(bounds) => bounds,
         None => return vec![],
     };
 
-    let mut result: Vec<String> = items
-        .iter()
-        .enumerate()
-        .filter(|(index, _)| {
-            if is_showing_complement {
-                !indexes_to_get.contains(index)
-            } else {
-                indexes_to_get.contains(index)
-            }
-        })
-        .map(|(_, item)| item.clone())
-        .collect();
+    // The indexes are already sorted, so no set is needed to order them
+    let mut result: Vec<usize> = (start..end).step_by(step.unsigned_abs() as usize).collect();
 
     if step < 0 {
         result.reverse();
@@ -87,16 +220,22 @@ fn cut_line(items: Vec<String>, range: Range, n: i32, is_showing_complement: boo
     result
 }
 
-fn calculate_indexes_to_get(start: i32, n: i32, end: i32, step: i32) -> Option<HashSet<usize>> {
-    //! Calculate the indexes that correspond to the range
+pub fn resolve_bounds(range: Range, n: i32) -> (i32, i32) {
+    //! Return the start and end of the range, with negative indexes counted from the end.
+    let (start, end, _) = range.to_tuple();
+    let resolve = |index: i32| if index >= 0 { index } else { n + index };
+
+    (resolve(start), resolve(end))
+}
+
+fn calculate_bounds(start: i32, n: i32, end: i32) -> Option<(usize, usize)> {
+    //! Calculate the start and the end of the indexes that correspond to the range
     let actual_start = handle_negative_index(start, n);
     let actual_end = handle_negative_index(end, n);
     if actual_start >= actual_end {
         return None;
     }
-    let actual_step = step.unsigned_abs() as usize;
-    let indexes_to_get: HashSet<usize> = (actual_start..actual_end).step_by(actual_step).collect();
-    Some(indexes_to_get)
+    Some((actual_start, actual_end))
 }
 
 fn handle_negative_index(index: i32, n: i32) -> usize {
@@ -107,11 +246,16 @@ fn handle_negative_index(index: i32, n: i32) -> usize {
     }
 }
 
-fn handle_bytes(byte: u8) -> String {
-    match String::from_utf8(vec![byte]) {
-        Ok(result) => result,
-        Err(_) => format!("{:#02x}", byte),
+fn handle_byte(line: &str, index: usize) -> &str {
+    //! Return the byte at the index. Bytes of multibyte characters are written in hexadecimal.
+    let byte = line.as_bytes()[index];
+    if byte.is_ascii() {
+        return &line[index..=index];
     }
+
+    let hex_bytes =
+        HEX_BYTES.get_or_init(|| (0..=u8::MAX).map(|byte| format!("{:#02x}", byte)).collect());
+    &hex_bytes[byte as usize]
 }
 
 #[cfg(test)]
@@ -146,7 +290,7 @@ mod unit_tests_cut_line_with_delimiter {
     static CONTENT: &str = "first second third fourth fifth";
     static DELIMITER: &str = " ";
 
-    static N: i32 = 5 as i32;
+    static N: i32 = 5;
 
     static START_A: i32 = -N - 3; // -8
     static START_B: i32 = -N + 1; // -4
@@ -621,12 +765,8 @@ mod unit_tests_cut_line_with_delimiter {
         let range = Range::new(start, end, step);
 
         // Act
-        let actual_content = cut_line_with_delimiter(
-            CONTENT,
-            range,
-            String::from(DELIMITER),
-            is_showing_complement,
-        );
+        let actual_content =
+            cut_line_with_delimiter(CONTENT, range, DELIMITER, is_showing_complement);
 
         // Assert
         assert_eq!(expected_content, actual_content);
@@ -664,7 +804,7 @@ mod unit_tests_cut_line_with_characters {
 
     static CONTENT: &str = "abcde";
 
-    static N: i32 = 5 as i32;
+    static N: i32 = 5;
 
     static START_A: i32 = -N - 3; // -8
     static START_B: i32 = -N + 1; // -4
@@ -1118,9 +1258,10 @@ mod unit_tests_cut_line_with_characters {
 
 #[cfg(test)]
 mod unit_tests_cut_line_with_bytes {
+    use crate::encoding::{decode_invalid_utf8, InvalidUtf8};
     use crate::range_parser::Range;
 
-    use super::cut_line_with_bytes;
+    use super::{cut_items, cut_line_with_bytes, split_bytes};
 
     /*
     start values:
@@ -1147,7 +1288,7 @@ mod unit_tests_cut_line_with_bytes {
 
     static CONTENT: &str = "abcde";
 
-    static N: i32 = 5 as i32;
+    static N: i32 = 5;
 
     static START_A: i32 = -N - 3; // -8
     static START_B: i32 = -N + 1; // -4
@@ -1597,6 +1738,20 @@ mod unit_tests_cut_line_with_bytes {
         assert_eq!(vec!["h", "0xc3", "0x80"], actual_content);
     }
 
+    #[test]
+    fn test_69_invalid_bytes_kept_as_they_are() {
+        // Arrange
+        let range = Range::new(1, 4, 1);
+        let content = decode_invalid_utf8(b"a\xe9\xffb\xc3\x80", InvalidUtf8::Bytes).unwrap();
+        let bytes = split_bytes(&content);
+
+        // Act
+        let actual_content = cut_items(&bytes, range, bytes.len() as i32, false);
+
+        // Assert
+        assert_eq!(vec!["\u{10ffe9}", "\u{10ffff}", "b"], actual_content);
+    }
+
     fn base_test(
         start: i32,
         end: i32,
@@ -1614,3 +1769,83 @@ mod unit_tests_cut_line_with_bytes {
         assert_eq!(expected_content, actual_content);
     }
 }
+
+#[cfg(test)]
+mod unit_tests_item_limit {
+    use crate::range_parser::Range;
+
+    use super::item_limit;
+
+    #[test]
+    fn test_01_highest_end() {
+        let ranges = [
+            Range::new(0, 1, 1),
+            Range::new(2, 6, 2),
+            Range::new(3, 4, 1),
+        ];
+
+        assert_eq!(7, item_limit(&ranges, false));
+    }
+
+    #[test]
+    fn test_02_negative_index() {
+        let ranges = [Range::new(0, 1, 1), Range::new(-3, -1, 1)];
+
+        assert_eq!(usize::MAX, item_limit(&ranges, false));
+    }
+
+    #[test]
+    fn test_03_complement() {
+        assert_eq!(usize::MAX, item_limit(&[Range::new(0, 1, 1)], true));
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_resolve_selection {
+    use crate::range_parser::Range;
+
+    use super::resolve_selection;
+
+    #[test]
+    fn test_01_ranges_in_order() {
+        let ranges = [Range::new(3, 5, 1), Range::new(0, 2, 1)];
+
+        assert_eq!(vec![3, 4, 0, 1], resolve_selection(&ranges, 5, false));
+    }
+
+    #[test]
+    fn test_02_complement_of_all_ranges() {
+        let ranges = [Range::new(1, 2, 1), Range::new(2, 5, 1)];
+
+        assert_eq!(vec![0], resolve_selection(&ranges, 5, true));
+    }
+
+    #[test]
+    fn test_03_complement_with_overlapping_ranges() {
+        let ranges = [Range::new(0, 6, 2), Range::new(1, 3, 1)];
+
+        assert_eq!(vec![3, 5], resolve_selection(&ranges, 6, true));
+    }
+
+    #[test]
+    fn test_04_complement_ignores_empty_ranges() {
+        let ranges = [Range::new(1, 2, 1), Range::new(3, 1, 1)];
+
+        assert_eq!(vec![0, 2, 3], resolve_selection(&ranges, 4, true));
+        assert_eq!(
+            Vec::<usize>::new(),
+            resolve_selection(&ranges[1..], 4, true)
+        );
+    }
+
+    #[test]
+    fn test_05_many_items() {
+        let ranges = [Range::new(0, 1000, 1), Range::new(2000, 10000, 1)];
+
+        let selection = resolve_selection(&ranges, 10000, true);
+
+        assert_eq!(1000, selection.len());
+        assert_eq!(Some(&1000), selection.first());
+        assert_eq!(Some(&1999), selection.last());
+    }
+}
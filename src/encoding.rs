@@ -0,0 +1,447 @@
+//! Module, containing the text encoding of the input and the output
+//!
+//! The input is decoded into UTF-8 before it is cut:
+//!     - with `--encoding NAME`, using the encoding NAME, e.g. `latin1`, `windows-1252`, `utf-16le` or `shift_jis`
+//!     - otherwise, a UTF-8 or UTF-16 byte order mark selects the encoding, and without one the input has to be UTF-8
+//!
+//! Invalid UTF-8 in a record is handled according to `--invalid-utf8`:
+//!     - `error` - stop with an error (the default)
+//!     - `replace` - replace each invalid sequence with U+FFFD
+//!     - `skip-line` - skip the record
+//!     - `bytes` - keep the invalid bytes as they are, and cut the record byte-wise around them
+//!
+//! With `bytes`, each invalid byte is decoded into a stand-in character from the end of the
+//! Supplementary Private Use Area-B (U+10FF80 to U+10FFFF), which counts as one byte for `-b`.
+//! Only the records that had invalid bytes are flagged as such, and only their stand-ins are written back
+//! as the bytes with `restore_raw_bytes`, so the same characters in the other records are left as they are.
+//!
+//! An input that is read in parts, with `-F`, `-j` or `--line-buffered`, is decoded by a `StreamDecoder`,
+//! which decides the encoding once, from its first bytes.
+//!
+//! A byte order mark is always removed. With `--output-encoding NAME` the output is encoded with NAME,
+//! where characters that NAME can't represent are written as `?`.
+//! ```rust
+//! decode(b"caf\xe9", Some(WINDOWS_1252)) => Ok("café")
+//! ```
+use std::io::{self, Write};
+
+use encoding_rs::{Decoder, Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+// The stand-in character of an invalid byte is this code point plus the byte
+static RAW_BYTE_BASE: u32 = 0x10FF00;
+// The byte order marks detected by `Encoding::for_bom`
+static BYTE_ORDER_MARKS: [&[u8]; 3] = [b"\xef\xbb\xbf", b"\xff\xfe", b"\xfe\xff"];
+
+pub fn parse_encoding(name: &str) -> Result<&'static Encoding, String> {
+    //! Get the encoding from one of its names, as defined in the WHATWG Encoding Standard
+    Encoding::for_label(name.as_bytes()).ok_or(format!("Unknown encoding \"{}\"", name))
+}
+
+pub fn decode(bytes: &[u8], encoding: Option<&'static Encoding>) -> Result<String, String> {
+    //! Decode the input into UTF-8.
+    //!
+    //! Without an encoding, the byte order mark is used to detect it, and the input defaults to UTF-8.
+    //! In that case invalid UTF-8 is an error, while with an explicit encoding invalid sequences are replaced with U+FFFD.
+    let encoding = match encoding {
+        Some(encoding) => encoding,
+        None => match Encoding::for_bom(bytes) {
+            Some((encoding, _)) => encoding,
+            None => {
+                return match std::str::from_utf8(bytes) {
+                    Ok(text) => Ok(text.to_owned()),
+                    Err(error) => Err(format!(
+                        "Input is not valid UTF-8 at byte {}, use --encoding to set its encoding or --invalid-utf8 to handle it",
+                        error.valid_up_to()
+                    )),
+                }
+            }
+        },
+    };
+
+    let (text, _) = encoding.decode_with_bom_removal(bytes);
+    Ok(text.into_owned())
+}
+
+/// Decoder of an input that is read in parts, such as the streamed standard input or a followed file
+///
+/// The encoding is decided once, from `--encoding` or from the byte order mark at the start of the input,
+/// so that the parts read later are decoded the same way, even if a character is split between two parts.
+/// Without either, the input is UTF-8 and its bytes are kept as they are, for `--invalid-utf8` to handle.
+pub struct StreamDecoder {
+    encoding: Option<&'static Encoding>,
+    state: DecoderState,
+}
+
+enum DecoderState {
+    // The first bytes, kept until it's known whether they start with a byte order mark
+    Undecided(Vec<u8>),
+    Utf8,
+    Decoding(Decoder),
+}
+
+impl StreamDecoder {
+    pub fn new(encoding: Option<&'static Encoding>) -> Self {
+        StreamDecoder {
+            encoding,
+            state: DecoderState::Undecided(vec![]),
+        }
+    }
+
+    pub fn decode(&mut self, bytes: &[u8], is_last: bool, output: &mut Vec<u8>) {
+        //! Decode the next bytes of the input into UTF-8, and append them to `output`.
+        //! `is_last` is set at the end of the input, so that an incomplete character isn't kept waiting.
+        if let DecoderState::Undecided(start) = &mut self.state {
+            start.extend_from_slice(bytes);
+            let may_have_bom = BYTE_ORDER_MARKS
+                .iter()
+                .any(|bom| bom.len() > start.len() && bom.starts_with(start));
+            if self.encoding.is_none() && may_have_bom && !is_last {
+                return;
+            }
+
+            let start = std::mem::take(start);
+            self.state = match self
+                .encoding
+                .or_else(|| Encoding::for_bom(&start).map(|(encoding, _)| encoding))
+            {
+                Some(encoding) => DecoderState::Decoding(encoding.new_decoder_with_bom_removal()),
+                None => DecoderState::Utf8,
+            };
+            return self.decode(&start, is_last, output);
+        }
+
+        match &mut self.state {
+            DecoderState::Decoding(decoder) => {
+                let length = output.len();
+                let max_length = decoder
+                    .max_utf8_buffer_length(bytes.len())
+                    .expect("The decoded part is too long");
+                output.resize(length + max_length, 0);
+                let (_, _, written, _) =
+                    decoder.decode_to_utf8(bytes, &mut output[length..], is_last);
+                output.truncate(length + written);
+            }
+            _ => output.extend_from_slice(bytes),
+        }
+    }
+}
+
+/// Enum that represents what happens to a record with invalid UTF-8
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum InvalidUtf8 {
+    Replace,
+    SkipLine,
+    Error,
+    Bytes,
+}
+
+impl InvalidUtf8 {
+    pub fn from_name(name: &str) -> Option<Self> {
+        //! Get the policy from its `--invalid-utf8` name
+        match name {
+            "replace" => Some(InvalidUtf8::Replace),
+            "skip-line" => Some(InvalidUtf8::SkipLine),
+            "error" => Some(InvalidUtf8::Error),
+            "bytes" => Some(InvalidUtf8::Bytes),
+            _ => None,
+        }
+    }
+}
+
+pub fn decode_invalid_utf8(bytes: &[u8], invalid_utf8: InvalidUtf8) -> Option<String> {
+    //! Decode a record with invalid UTF-8. Return `None` if the record should be skipped.
+    match invalid_utf8 {
+        InvalidUtf8::SkipLine => None,
+        InvalidUtf8::Bytes => {
+            let mut text = String::new();
+            for chunk in bytes.utf8_chunks() {
+                for character in chunk.valid().chars() {
+                    // A real stand-in character is kept as its bytes, so that every stand-in of the record is a byte
+                    match raw_byte(character) {
+                        Some(_) => text.extend(
+                            character
+                                .encode_utf8(&mut [0; 4])
+                                .bytes()
+                                .map(raw_byte_character),
+                        ),
+                        None => text.push(character),
+                    }
+                }
+                text.extend(chunk.invalid().iter().map(|byte| raw_byte_character(*byte)));
+            }
+            Some(text)
+        }
+        InvalidUtf8::Replace | InvalidUtf8::Error => {
+            Some(String::from_utf8_lossy(bytes).into_owned())
+        }
+    }
+}
+
+fn raw_byte_character(byte: u8) -> char {
+    //! Get the stand-in character of a byte of a record with invalid bytes. These bytes are never ASCII.
+    char::from_u32(RAW_BYTE_BASE + byte as u32).unwrap()
+}
+
+pub fn raw_byte(character: char) -> Option<u8> {
+    //! Get the invalid byte that the character stands in for, if it is a stand-in character
+    match (character as u32).checked_sub(RAW_BYTE_BASE) {
+        Some(byte @ 0x80..=0xFF) => Some(byte as u8),
+        _ => None,
+    }
+}
+
+pub fn restore_raw_bytes(text: &str) -> Vec<u8> {
+    //! Turn the stand-in characters of a record with invalid bytes back into the bytes
+    let mut bytes = Vec::with_capacity(text.len());
+    for character in text.chars() {
+        match raw_byte(character) {
+            Some(byte) => bytes.push(byte),
+            None => bytes.extend_from_slice(character.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    bytes
+}
+
+/// Writer that encodes the UTF-8 output with another encoding
+pub struct EncodingWriter<W: Write> {
+    output: W,
+    encoding: &'static Encoding,
+    // Bytes of a character that was split between two writes
+    pending: Vec<u8>,
+}
+
+impl<W: Write> EncodingWriter<W> {
+    pub fn new(output: W, encoding: &'static Encoding) -> Self {
+        EncodingWriter {
+            output,
+            encoding,
+            pending: vec![],
+        }
+    }
+}
+
+impl<W: Write> Write for EncodingWriter<W> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buffer);
+
+        let valid_length = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            Err(error) if error.error_len().is_none() => error.valid_up_to(),
+            Err(_) => return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid UTF-8")),
+        };
+
+        let text = std::str::from_utf8(&self.pending[..valid_length]).unwrap();
+        let encoded = encode(text, self.encoding);
+        self.output.write_all(&encoded)?;
+        self.pending.drain(..valid_length);
+
+        Ok(buffer.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+fn encode(text: &str, encoding: &'static Encoding) -> Vec<u8> {
+    //! Encode the text, replacing the characters that can't be encoded with `?`
+    if encoding == UTF_16LE {
+        return text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    } else if encoding == UTF_16BE {
+        return text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    } else if encoding == UTF_8 {
+        return text.as_bytes().to_vec();
+    }
+
+    let mut encoded = vec![];
+    for character in text.chars() {
+        let mut buffer = [0; 4];
+        let (bytes, _, has_unmappable) = encoding.encode(character.encode_utf8(&mut buffer));
+
+        if has_unmappable {
+            encoded.push(b'?');
+        } else {
+            encoded.extend_from_slice(&bytes);
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod unit_tests_decode {
+    use super::{decode, parse_encoding};
+
+    #[test]
+    fn test_01_utf8() {
+        assert_eq!(Ok(String::from("café")), decode("café".as_bytes(), None));
+    }
+
+    #[test]
+    fn test_02_utf8_with_bom() {
+        assert_eq!(Ok(String::from("a,b")), decode(b"\xef\xbb\xbfa,b", None));
+    }
+
+    #[test]
+    fn test_03_utf16_with_bom() {
+        assert_eq!(
+            Ok(String::from("ñ,b")),
+            decode(b"\xff\xfe\xf1\x00,\x00b\x00", None)
+        );
+        assert_eq!(
+            Ok(String::from("ñ,b")),
+            decode(b"\xfe\xff\x00\xf1\x00,\x00b", None)
+        );
+    }
+
+    #[test]
+    fn test_04_invalid_utf8() {
+        assert!(decode(b"caf\xe9", None).is_err());
+    }
+
+    #[test]
+    fn test_05_explicit_encoding() {
+        let latin1 = parse_encoding("latin1").unwrap();
+        let utf16 = parse_encoding("utf-16le").unwrap();
+
+        assert_eq!(Ok(String::from("café")), decode(b"caf\xe9", Some(latin1)));
+        assert_eq!(Ok(String::from("€")), decode(b"\x80", Some(latin1)));
+        assert_eq!(Ok(String::from("ab")), decode(b"a\x00b\x00", Some(utf16)));
+    }
+
+    #[test]
+    fn test_06_unknown_encoding() {
+        assert!(parse_encoding("klingon").is_err());
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_stream_decoder {
+    use super::{parse_encoding, StreamDecoder};
+
+    #[test]
+    fn test_01_utf8_is_kept_as_it_is() {
+        base_test(None, &[b"a,\xe9", b"b\n"], b"a,\xe9b\n");
+    }
+
+    #[test]
+    fn test_02_utf16_bom_decides_the_encoding() {
+        base_test(
+            None,
+            &[b"\xff\xfea\x00,\x00b\x00\n", b"\x00c\x00,\x00d\x00\n\x00"],
+            b"a,b\nc,d\n",
+        );
+    }
+
+    #[test]
+    fn test_03_bom_split_between_parts() {
+        base_test(None, &[b"\xef", b"\xbb", b"\xbfa\n"], b"a\n");
+        base_test(None, &[b"\xfe", b"\xff\x00a"], b"a");
+    }
+
+    #[test]
+    fn test_04_explicit_encoding() {
+        let latin1 = parse_encoding("latin1").unwrap();
+
+        base_test(Some(latin1), &[b"caf", b"\xe9\n"], "café\n".as_bytes());
+    }
+
+    #[test]
+    fn test_05_decided_without_a_bom() {
+        let mut decoder = StreamDecoder::new(None);
+        let mut output = vec![];
+
+        decoder.decode(b"a\n", false, &mut output);
+
+        assert_eq!(b"a\n".to_vec(), output);
+    }
+
+    fn base_test(
+        encoding: Option<&'static encoding_rs::Encoding>,
+        parts: &[&[u8]],
+        expected: &[u8],
+    ) {
+        let mut decoder = StreamDecoder::new(encoding);
+        let mut output = vec![];
+
+        for part in parts {
+            decoder.decode(part, false, &mut output);
+        }
+        decoder.decode(&[], true, &mut output);
+
+        assert_eq!(expected.to_vec(), output);
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_encoding_writer {
+    use super::{parse_encoding, EncodingWriter};
+    use std::io::Write;
+
+    #[test]
+    fn test_01_legacy_encoding() {
+        base_test("windows-1252", &["café €\n"], b"caf\xe9 \x80\n");
+    }
+
+    #[test]
+    fn test_02_unmappable_characters() {
+        base_test("latin1", &["a│b\n"], b"a?b\n");
+    }
+
+    #[test]
+    fn test_03_utf16() {
+        base_test("utf-16le", &["añ"], b"a\x00\xf1\x00");
+        base_test("utf-16be", &["añ"], b"\x00a\x00\xf1");
+    }
+
+    #[test]
+    fn test_04_character_split_between_writes() {
+        let mut output = vec![];
+        let mut writer = EncodingWriter::new(&mut output, parse_encoding("latin1").unwrap());
+
+        writer.write_all(&[b'a', 0xc3]).unwrap();
+        writer.write_all(&[0xa9, b'b']).unwrap();
+
+        assert_eq!(b"a\xe9b".to_vec(), output);
+    }
+
+    fn base_test(encoding: &str, writes: &[&str], expected: &[u8]) {
+        let mut output = vec![];
+        let mut writer = EncodingWriter::new(&mut output, parse_encoding(encoding).unwrap());
+
+        for text in writes {
+            writer.write_all(text.as_bytes()).unwrap();
+        }
+
+        assert_eq!(expected.to_vec(), output);
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_raw_bytes {
+    use super::{decode_invalid_utf8, restore_raw_bytes, InvalidUtf8};
+
+    #[test]
+    fn test_01_invalid_bytes_are_kept() {
+        let text = decode_invalid_utf8(b"caf\xe9,\xff\x80", InvalidUtf8::Bytes).unwrap();
+
+        assert_eq!(7, text.chars().count());
+        assert_eq!(b"caf\xe9,\xff\x80".to_vec(), restore_raw_bytes(&text));
+    }
+
+    #[test]
+    fn test_02_valid_characters_are_unchanged() {
+        let text = decode_invalid_utf8("añ\n".as_bytes(), InvalidUtf8::Bytes).unwrap();
+
+        assert_eq!("añ\n", text);
+        assert_eq!("añ\n".as_bytes().to_vec(), restore_raw_bytes(&text));
+    }
+
+    #[test]
+    fn test_03_real_stand_in_character_with_invalid_bytes() {
+        let bytes = "\u{10ffff},".bytes().chain([0xe9]).collect::<Vec<u8>>();
+        let text = decode_invalid_utf8(&bytes, InvalidUtf8::Bytes).unwrap();
+
+        assert_eq!(6, text.chars().count());
+        assert_eq!(bytes, restore_raw_bytes(&text));
+    }
+}
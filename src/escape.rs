@@ -0,0 +1,125 @@
+//! Module, containing the backslash escape sequences accepted by `-d` and `--output_delimiter`
+//!
+//! The supported sequences are:
+//!     - `\t`, `\n`, `\r` - TAB, line feed and carriage return
+//!     - `\0` - NUL
+//!     - `\\` - a backslash
+//!     - `\xHH` - the ASCII character with the hexadecimal code `HH`, e.g. `\x1f` (unit separator)
+//!     - `\u{H...}` - the Unicode character with the hexadecimal code point, e.g. `\u{2502}` (`│`)
+//!
+//! Any other sequence is an error. Use `--literal-delimiter` to pass the delimiters as they are.
+//! ```rust
+//! unescape("\\x1f") => Ok("\u{1f}")
+//! ```
+
+pub fn unescape(input: &str) -> Result<String, String> {
+    //! Replace the escape sequences in the input with the characters they represent.
+    let mut result = String::new();
+    let mut characters = input.chars();
+
+    while let Some(character) = characters.next() {
+        if character != '\\' {
+            result.push(character);
+            continue;
+        }
+
+        let escaped = match characters.next() {
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('x') => {
+                let digits: String = characters.by_ref().take(2).collect();
+                let is_valid = digits.len() == 2 && digits.chars().all(|c| c.is_ascii_hexdigit());
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(code) if is_valid && code.is_ascii() => code as char,
+                    _ => return Err(format!("Invalid escape sequence \"\\x{}\"", digits)),
+                }
+            }
+            Some('u') => {
+                let code = parse_unicode_code(&mut characters)?;
+                match char::from_u32(code) {
+                    Some(character) => character,
+                    None => return Err(format!("Invalid Unicode code point \"{:x}\"", code)),
+                }
+            }
+            Some(other) => return Err(format!("Invalid escape sequence \"\\{}\"", other)),
+            None => return Err(String::from("Invalid escape sequence \"\\\" at the end")),
+        };
+        result.push(escaped);
+    }
+
+    Ok(result)
+}
+
+fn parse_unicode_code(characters: &mut std::str::Chars) -> Result<u32, String> {
+    //! Parse the `{H...}` part of a `\u{H...}` sequence
+    let error_message = String::from("Invalid escape sequence, expected \"\\u{H...}\"");
+
+    if characters.next() != Some('{') {
+        return Err(error_message);
+    }
+
+    let mut code = String::new();
+    loop {
+        match characters.next() {
+            Some('}') => break,
+            Some(character) => code.push(character),
+            None => return Err(error_message),
+        }
+    }
+
+    if code.is_empty() || !code.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(error_message);
+    }
+
+    u32::from_str_radix(&code, 16).map_err(|_| error_message)
+}
+
+#[cfg(test)]
+mod unit_tests_unescape {
+    use super::unescape;
+
+    #[test]
+    fn test_01_no_escapes() {
+        base_test(",", ",");
+        base_test("::", "::");
+    }
+
+    #[test]
+    fn test_02_simple_escapes() {
+        base_test("\\t", "\t");
+        base_test("\\n", "\n");
+        base_test("\\r\\n", "\r\n");
+        base_test("\\0", "\0");
+        base_test("\\\\", "\\");
+    }
+
+    #[test]
+    fn test_03_hexadecimal() {
+        base_test("\\x1f", "\u{1f}");
+        base_test("a\\x2Cb", "a,b");
+    }
+
+    #[test]
+    fn test_04_unicode() {
+        base_test("\\u{2502}", "│");
+        base_test(" \\u{1F600} ", " 😀 ");
+    }
+
+    #[test]
+    fn test_05_invalid_escapes() {
+        assert!(unescape("\\q").is_err());
+        assert!(unescape("\\").is_err());
+        assert!(unescape("\\x1").is_err());
+        assert!(unescape("\\xff").is_err());
+        assert!(unescape("\\u2502").is_err());
+        assert!(unescape("\\u{2502").is_err());
+        assert!(unescape("\\u{d800}").is_err());
+    }
+
+    fn base_test(input: &str, expected: &str) {
+        assert_eq!(Ok(String::from(expected)), unescape(input));
+    }
+}
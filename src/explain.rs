@@ -0,0 +1,177 @@
+//! Module, containing the `--explain` output
+//!
+//! Instead of the cut line, show how each range is applied to it:
+//! the parsed `Range`, its bounds after handling negative indexes,
+//! the selected indexes and the items at these indexes.
+//! With `--complement`, the ranges are merged into one selection, shown once after them.
+//!
+//! ```text
+//! Line 1: ID,Name,Age,Email,City,Country,Occupation,Salary
+//!   Items: 8
+//!   Range -5:-1:-2 => 3:7:-2
+//!     Indexes: [5, 3]
+//!     [5] Country
+//!     [3] Email
+//!   Output: Country,Email
+//! ```
+use crate::cut::{resolve_bounds, resolve_indexes, resolve_selection};
+use crate::range_parser::Range;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExplainMode {
+    First,
+    All,
+}
+
+pub fn explain_line(
+    line_number: usize,
+    line: &str,
+    items: &[&str],
+    n: i32,
+    ranges: &Result<Vec<Range>, String>,
+    is_showing_complement: bool,
+    output: &str,
+) -> String {
+    //! Build the explanation of how the ranges select items from the line.
+    //! `n` is the length of the line used when resolving the ranges.
+    let mut explanation = vec![
+        format!("Line {}: {}", line_number, line),
+        format!("  Items: {}", n),
+    ];
+
+    let ranges = match ranges {
+        Ok(ranges) => ranges,
+        Err(error) => {
+            explanation.push(format!("  Error: {}", error));
+            return explanation.join("\n");
+        }
+    };
+
+    for range in ranges {
+        let (start, end) = resolve_bounds(*range, n);
+        let (_, _, step) = range.to_tuple();
+        explanation.push(format!("  Range {} => {}:{}:{}", range, start, end, step));
+
+        if !is_showing_complement {
+            let indexes = resolve_indexes(*range, n, false);
+            explain_indexes(&mut explanation, &indexes, items);
+        }
+    }
+
+    if is_showing_complement {
+        explanation.push(String::from("  Complement of the ranges"));
+        let indexes = resolve_selection(ranges, n, true);
+        explain_indexes(&mut explanation, &indexes, items);
+    }
+
+    explanation.push(format!("  Output: {}", output));
+
+    explanation.join("\n")
+}
+
+fn explain_indexes(explanation: &mut Vec<String>, indexes: &[usize], items: &[&str]) {
+    //! Add the selected indexes and the items at these indexes to the explanation
+    explanation.push(format!("    Indexes: {:?}", indexes));
+
+    for index in indexes {
+        if let Some(item) = items.get(*index) {
+            explanation.push(format!("    [{}] {}", index, item));
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_explain_line {
+    use super::explain_line;
+    use crate::range_parser::Range;
+
+    static LINE: &str = "first second third fourth fifth";
+
+    #[test]
+    fn test_01_negative_range() {
+        let expected = "Line 1: first second third fourth fifth
+  Items: 5
+  Range -4:-1:-2 => 1:4:-2
+    Indexes: [3, 1]
+    [3] fourth
+    [1] second
+  Output: fourth second";
+
+        base_test(
+            Ok(vec![Range::new(-4, -1, -2)]),
+            false,
+            "fourth second",
+            expected,
+        );
+    }
+
+    #[test]
+    fn test_02_out_of_bounds_range() {
+        let expected = "Line 1: first second third fourth fifth
+  Items: 5
+  Range 3:9:1 => 3:9:1
+    Indexes: []
+  Output: ";
+
+        base_test(Ok(vec![Range::new(3, 9, 1)]), false, "", expected);
+    }
+
+    #[test]
+    fn test_03_complement() {
+        let expected = "Line 1: first second third fourth fifth
+  Items: 5
+  Range 1:4:1 => 1:4:1
+  Complement of the ranges
+    Indexes: [0, 4]
+    [0] first
+    [4] fifth
+  Output: first fifth";
+
+        base_test(Ok(vec![Range::new(1, 4, 1)]), true, "first fifth", expected);
+    }
+
+    #[test]
+    fn test_04_invalid_range() {
+        let expected = "Line 1: first second third fourth fifth
+  Items: 5
+  Error: Invalid range";
+
+        base_test(Err(String::from("Invalid range")), false, "", expected);
+    }
+
+    #[test]
+    fn test_05_complement_of_several_ranges() {
+        let expected = "Line 1: first second third fourth fifth
+  Items: 5
+  Range 1:2:1 => 1:2:1
+  Range 3:4:1 => 3:4:1
+  Complement of the ranges
+    Indexes: [0, 2, 4]
+    [0] first
+    [2] third
+    [4] fifth
+  Output: first third fifth";
+
+        base_test(
+            Ok(vec![Range::new(1, 2, 1), Range::new(3, 4, 1)]),
+            true,
+            "first third fifth",
+            expected,
+        );
+    }
+
+    fn base_test(
+        ranges: Result<Vec<Range>, String>,
+        is_showing_complement: bool,
+        output: &str,
+        expected: &str,
+    ) {
+        let items: Vec<&str> = LINE.split(' ').collect();
+
+        let n = items.len() as i32;
+
+        let actual = explain_line(1, LINE, &items, n, &ranges, is_showing_complement, output);
+
+        assert_eq!(expected, actual);
+    }
+}
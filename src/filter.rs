@@ -0,0 +1,434 @@
+//! Module, containing the row filtering abstractions used by `--where`
+//!
+//! A filter is made up of one or more conditions, combined with `and` and `or`.
+//! `and` binds tighter than `or`, so `a and b or c` is `(a and b) or c`.
+//!
+//! Each condition has the form `FIELD OP VALUE`:
+//!     - `FIELD` is a field index, using the same indexing as `-f` (`-1` is the last field).
+//!       In header mode it can also be a column name from the first line.
+//!     - `OP` is one of `==`, `!=`, `<`, `<=`, `>`, `>=`, `~` (regex match) and `!~` (regex does not match)
+//!     - `VALUE` is compared numerically if both sides are finite numbers, otherwise as strings.
+//!       It can be put in single or double quotes, so that it may contain `and` and `or`.
+//!
+//! ```rust
+//! parse_filter("2>=30 and -1~^Software") => Filter([[2 >= 30, -1 ~ ^Software]])
+//! ```
+use regex::Regex;
+use std::cmp::Ordering;
+use std::ops::Range;
+
+/// Struct that represents a parsed `--where` expression
+/// The conditions are stored as alternatives (`or`) of conjunctions (`and`)
+#[derive(Debug)]
+pub struct Filter {
+    alternatives: Vec<Vec<Condition>>,
+}
+
+#[derive(Debug)]
+struct Condition {
+    field: FieldReference,
+    operator: Operator,
+    value: String,
+    pattern: Option<Regex>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum FieldReference {
+    Index(i32),
+    Name(String),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Matches,
+    NotMatches,
+}
+
+static OPERATOR_CHARACTERS: [char; 5] = ['=', '!', '<', '>', '~'];
+
+// Longer operators come first, so that `<=` is not parsed as `<`
+static OPERATORS: [(&str, Operator); 8] = [
+    ("==", Operator::Equal),
+    ("!=", Operator::NotEqual),
+    ("<=", Operator::LessOrEqual),
+    (">=", Operator::GreaterOrEqual),
+    ("!~", Operator::NotMatches),
+    ("<", Operator::Less),
+    (">", Operator::Greater),
+    ("~", Operator::Matches),
+];
+
+impl Filter {
+    pub fn resolve_names(&mut self, header: &[&str]) -> Result<(), String> {
+        //! Replace the column names in the conditions with their index in the `header`.
+        for condition in self.alternatives.iter_mut().flatten() {
+            if let FieldReference::Name(name) = &condition.field {
+                match header.iter().position(|column| column == name) {
+                    Some(index) => condition.field = FieldReference::Index(index as i32),
+                    None => return Err(format!("Unknown column \"{}\"", name)),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn has_names(&self) -> bool {
+        //! Check if any of the conditions refers to a field by its column name
+        self.alternatives
+            .iter()
+            .flatten()
+            .any(|condition| matches!(condition.field, FieldReference::Name(_)))
+    }
+
+    pub fn matches(&self, fields: &[&str]) -> bool {
+        //! Evaluate the filter against the fields of a line.
+        self.alternatives
+            .iter()
+            .any(|conditions| conditions.iter().all(|condition| condition.matches(fields)))
+    }
+}
+
+impl Condition {
+    fn matches(&self, fields: &[&str]) -> bool {
+        //! Evaluate a single condition. If the field does not exist on the line, the condition is false.
+        let field = match self.field {
+            FieldReference::Index(index) => get_field(fields, index),
+            FieldReference::Name(_) => None,
+        };
+
+        let field = match field {
+            Some(field) => field,
+            None => return false,
+        };
+
+        match self.operator {
+            Operator::Matches => self.pattern.as_ref().is_some_and(|re| re.is_match(field)),
+            Operator::NotMatches => self.pattern.as_ref().is_some_and(|re| !re.is_match(field)),
+            operator => {
+                let ordering = compare(field, &self.value);
+                match operator {
+                    Operator::Equal => ordering == Some(Ordering::Equal),
+                    Operator::NotEqual => ordering != Some(Ordering::Equal),
+                    Operator::Less => ordering == Some(Ordering::Less),
+                    Operator::LessOrEqual => {
+                        matches!(ordering, Some(Ordering::Less | Ordering::Equal))
+                    }
+                    Operator::Greater => ordering == Some(Ordering::Greater),
+                    Operator::GreaterOrEqual => {
+                        matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+                    }
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+}
+
+pub fn parse_filter(input: &str) -> Result<Filter, String> {
+    //! Convert a `--where` expression into a `Filter`.
+    //! If one of the conditions is not parsable, the whole expression is deemed unparsable.
+    let mut alternatives = vec![];
+
+    for alternative in split_on_keyword(input, "or") {
+        let mut conditions = vec![];
+        for condition in split_on_keyword(&alternative, "and") {
+            conditions.push(parse_condition(&condition)?);
+        }
+        alternatives.push(conditions);
+    }
+
+    Ok(Filter { alternatives })
+}
+
+fn split_on_keyword(input: &str, keyword: &str) -> Vec<String> {
+    //! Split the input on a keyword, which has to be surrounded by whitespace.
+    //! A keyword inside a quoted value does not split the input.
+    let separator = Regex::new(&format!(r"\s+{}\s+", keyword)).unwrap();
+    let quoted = find_quoted_values(input);
+
+    let mut parts = vec![];
+    let mut start = 0;
+    for found in separator.find_iter(input) {
+        if quoted.iter().any(|span| span.contains(&found.start())) {
+            continue;
+        }
+        parts.push(input[start..found.start()].to_string());
+        start = found.end();
+    }
+    parts.push(input[start..].to_string());
+
+    parts
+}
+
+fn find_quoted_values(input: &str) -> Vec<Range<usize>> {
+    //! Find the byte spans of the quoted values in the input.
+    //!
+    //! A value is quoted if it starts with `'` or `"` right after an operator, so that quotes
+    //! inside a value, e.g. `0==O'Brien`, are kept as they are. An unclosed quote runs to the end of the input.
+    let mut spans = vec![];
+    let mut previous = None;
+    let mut characters = input.char_indices();
+
+    while let Some((start, character)) = characters.next() {
+        let after_operator =
+            previous.is_some_and(|previous| OPERATOR_CHARACTERS.contains(&previous));
+
+        if after_operator && (character == '\'' || character == '"') {
+            let end = characters
+                .find(|(_, closing)| *closing == character)
+                .map_or(input.len(), |(end, _)| end + 1);
+            spans.push(start..end);
+            previous = Some(character);
+        } else if !character.is_whitespace() {
+            previous = Some(character);
+        }
+    }
+
+    spans
+}
+
+fn unquote(value: &str) -> &str {
+    //! Remove the quotes around a value, if it has them
+    for quote in ['\'', '"'] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            return &value[1..value.len() - 1];
+        }
+    }
+
+    value
+}
+
+fn parse_condition(input: &str) -> Result<Condition, String> {
+    //! Parse a single `FIELD OP VALUE` condition.
+    //!
+    //! The operator is the first operator found in the input.
+    let error_message = format!("Invalid condition \"{}\"", input);
+
+    let operator_start = match input.find(OPERATOR_CHARACTERS) {
+        Some(index) => index,
+        None => return Err(error_message),
+    };
+
+    let (token, operator) = match OPERATORS
+        .iter()
+        .find(|(token, _)| input[operator_start..].starts_with(token))
+    {
+        Some(found) => *found,
+        None => return Err(error_message),
+    };
+
+    let raw_field = input[..operator_start].trim();
+    let value = unquote(input[operator_start + token.len()..].trim()).to_string();
+
+    if raw_field.is_empty() {
+        return Err(error_message);
+    }
+
+    let field = match raw_field.parse::<i32>() {
+        Ok(index) => FieldReference::Index(index),
+        Err(_) => FieldReference::Name(raw_field.to_string()),
+    };
+
+    let pattern = match operator {
+        Operator::Matches | Operator::NotMatches => match Regex::new(&value) {
+            Ok(pattern) => Some(pattern),
+            Err(_) => return Err(format!("Invalid regular expression \"{}\"", value)),
+        },
+        _ => None,
+    };
+
+    Ok(Condition {
+        field,
+        operator,
+        value,
+        pattern,
+    })
+}
+
+fn get_field<'a>(fields: &[&'a str], index: i32) -> Option<&'a str> {
+    //! Get a field by a Python-style index
+    let n = fields.len() as i32;
+    let actual_index = if index >= 0 { index } else { n + index };
+
+    if 0 <= actual_index && actual_index < n {
+        Some(fields[actual_index as usize])
+    } else {
+        None
+    }
+}
+
+fn compare(field: &str, value: &str) -> Option<Ordering> {
+    //! Compare numerically if both sides are finite numbers, otherwise compare as strings.
+    //! `nan` and `inf` are compared as strings, so that they match only themselves.
+    match (parse_finite(field.trim()), parse_finite(value)) {
+        (Some(field), Some(value)) => field.partial_cmp(&value),
+        _ => Some(field.cmp(value)),
+    }
+}
+
+fn parse_finite(input: &str) -> Option<f64> {
+    //! Parse a number, so that `inf` and `NaN` are compared as strings
+    input
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+}
+
+#[cfg(test)]
+mod unit_tests_parse_filter {
+    use super::{parse_filter, FieldReference, Operator};
+
+    #[test]
+    fn test_01_numeric_comparison() {
+        let filter = parse_filter("2>=30").unwrap();
+        let condition = &filter.alternatives[0][0];
+
+        assert_eq!(FieldReference::Index(2), condition.field);
+        assert_eq!(Operator::GreaterOrEqual, condition.operator);
+        assert_eq!("30", condition.value);
+    }
+
+    #[test]
+    fn test_02_negative_index_regex() {
+        let filter = parse_filter("-1~^Software").unwrap();
+        let condition = &filter.alternatives[0][0];
+
+        assert_eq!(FieldReference::Index(-1), condition.field);
+        assert_eq!(Operator::Matches, condition.operator);
+        assert!(condition.pattern.is_some());
+    }
+
+    #[test]
+    fn test_03_column_name() {
+        let filter = parse_filter("Name==Bob").unwrap();
+        let condition = &filter.alternatives[0][0];
+
+        assert_eq!(FieldReference::Name(String::from("Name")), condition.field);
+        assert_eq!(Operator::Equal, condition.operator);
+    }
+
+    #[test]
+    fn test_04_and_binds_tighter_than_or() {
+        let filter = parse_filter("1 > 2 and 2 < 3 or 4 != 5").unwrap();
+
+        assert_eq!(2, filter.alternatives.len());
+        assert_eq!(2, filter.alternatives[0].len());
+        assert_eq!(1, filter.alternatives[1].len());
+    }
+
+    #[test]
+    fn test_05_missing_operator() {
+        assert!(parse_filter("2 30").is_err());
+    }
+
+    #[test]
+    fn test_06_missing_field() {
+        assert!(parse_filter("==30").is_err());
+    }
+
+    #[test]
+    fn test_07_invalid_regex() {
+        assert!(parse_filter("1~(").is_err());
+    }
+
+    #[test]
+    fn test_08_quoted_value_with_keyword() {
+        let filter = parse_filter("1=='Research and Development' or 2 == \"a or b\"").unwrap();
+
+        assert_eq!(2, filter.alternatives.len());
+        assert_eq!("Research and Development", filter.alternatives[0][0].value);
+        assert_eq!("a or b", filter.alternatives[1][0].value);
+    }
+
+    #[test]
+    fn test_09_quote_inside_value() {
+        let filter = parse_filter("0==O'Brien and 1>3").unwrap();
+
+        assert_eq!(2, filter.alternatives[0].len());
+        assert_eq!("O'Brien", filter.alternatives[0][0].value);
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_filter_matches {
+    use super::parse_filter;
+
+    static FIELDS: [&str; 4] = ["John Doe", "35", "75000", "Software Engineer"];
+
+    #[test]
+    fn test_01_numeric_comparison() {
+        base_test("1>=30", true);
+        base_test("1<30", false);
+    }
+
+    #[test]
+    fn test_02_numeric_comparison_is_not_lexicographic() {
+        base_test("2>9000", true);
+    }
+
+    #[test]
+    fn test_03_string_comparison() {
+        base_test("0==John Doe", true);
+        base_test("0!=John Doe", false);
+    }
+
+    #[test]
+    fn test_04_regex() {
+        base_test("-1~^Software", true);
+        base_test("-1!~^Software", false);
+    }
+
+    #[test]
+    fn test_05_out_of_bounds_field() {
+        base_test("10==John Doe", false);
+    }
+
+    #[test]
+    fn test_06_and_or() {
+        base_test("1>40 and 2>0 or 0~Doe", true);
+        base_test("1>40 or 2>0 and 0~Smith", false);
+    }
+
+    #[test]
+    fn test_07_nan_is_compared_as_string() {
+        let filter = parse_filter("0!=nan").unwrap();
+
+        assert!(!filter.matches(&["nan"]));
+        assert!(filter.matches(&["1.5"]));
+    }
+
+    #[test]
+    fn test_08_quoted_value() {
+        base_test("-1=='Software Engineer' and 0==\"John Doe\"", true);
+    }
+
+    #[test]
+    fn test_09_resolve_names() {
+        let mut filter = parse_filter("Age>30").unwrap();
+        filter
+            .resolve_names(&["Name", "Age", "Salary", "Occupation"])
+            .unwrap();
+
+        assert!(filter.matches(&FIELDS));
+    }
+
+    #[test]
+    fn test_10_resolve_unknown_name() {
+        let mut filter = parse_filter("Height>30").unwrap();
+
+        assert!(filter.resolve_names(&["Name", "Age"]).is_err());
+    }
+
+    fn base_test(input: &str, expected: bool) {
+        let filter = parse_filter(input).unwrap();
+
+        assert_eq!(expected, filter.matches(&FIELDS));
+    }
+}
@@ -0,0 +1,119 @@
+//! Module, containing the JSON Lines input handling used by `--json`
+//!
+//! Each line is parsed as a JSON value and the LIST is a comma separated list of paths.
+//! A path is made of keys and array positions, separated by `.`:
+//!     - `ts` - the value of the key `ts`
+//!     - `user.id` - the value of the key `id` in the object under `user`
+//!     - `0`, `-1` - the first and the last element of an array (Python-style indexing)
+//!     - `tags.0` - the first element of the array under `tags`
+//!
+//! Strings are output as they are, `null` and missing values as an empty string,
+//! and everything else as JSON.
+//! ```rust
+//! select_paths("{\"user\": {\"id\": 7}}", parse_paths("user.id,ts")) => Ok(["7", ""])
+//! ```
+use serde_json::Value;
+
+pub fn parse_paths(list: &str) -> Vec<Vec<String>> {
+    //! Split the LIST into paths, and each path into its keys
+    list.split(',')
+        .map(|path| path.split('.').map(String::from).collect())
+        .collect()
+}
+
+pub fn select_paths(line: &str, paths: &[Vec<String>]) -> Result<Vec<String>, String> {
+    //! Parse the line as JSON and return the value at each path.
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(error) => return Err(format!("Invalid JSON: {}", error)),
+    };
+
+    Ok(paths
+        .iter()
+        .map(|path| match get_path(&value, path) {
+            Some(Value::String(text)) => text.clone(),
+            Some(Value::Null) | None => String::new(),
+            Some(other) => other.to_string(),
+        })
+        .collect())
+}
+
+fn get_path<'a>(value: &'a Value, path: &[String]) -> Option<&'a Value> {
+    //! Follow the path from the value. If a key or position does not exist, return `None`.
+    let mut current = value;
+
+    for key in path {
+        current = match current {
+            Value::Object(object) => object.get(key)?,
+            Value::Array(array) => {
+                let n = array.len() as i64;
+                let index = key.parse::<i64>().ok()?;
+                let actual_index = if index >= 0 { index } else { n + index };
+
+                if actual_index < 0 {
+                    return None;
+                }
+                array.get(actual_index as usize)?
+            }
+            _ => return None,
+        };
+    }
+
+    Some(current)
+}
+
+#[cfg(test)]
+mod unit_tests_select_paths {
+    use super::{parse_paths, select_paths};
+
+    static LINE: &str = r#"{"ts": "2024-01-01T10:00:00", "level": "info", "user": {"id": 7, "name": "Bob"}, "tags": ["a", "b", "c"], "ok": true, "error": null}"#;
+
+    #[test]
+    fn test_01_top_level_key() {
+        base_test(LINE, "ts,level", vec!["2024-01-01T10:00:00", "info"]);
+    }
+
+    #[test]
+    fn test_02_nested_key() {
+        base_test(LINE, "user.id,user.name", vec!["7", "Bob"]);
+    }
+
+    #[test]
+    fn test_03_array_positions() {
+        base_test(LINE, "tags.0,tags.-1", vec!["a", "c"]);
+    }
+
+    #[test]
+    fn test_04_non_string_values() {
+        base_test(
+            LINE,
+            "ok,error,tags",
+            vec!["true", "", "[\"a\",\"b\",\"c\"]"],
+        );
+    }
+
+    #[test]
+    fn test_05_missing_values() {
+        base_test(LINE, "missing,user.email,tags.5,ts.0", vec!["", "", "", ""]);
+    }
+
+    #[test]
+    fn test_06_top_level_array() {
+        base_test("[1, \"two\", 3.5]", "0,-2", vec!["1", "two"]);
+    }
+
+    #[test]
+    fn test_07_invalid_json() {
+        let paths = parse_paths("ts");
+
+        assert!(select_paths("{\"ts\": ", &paths).is_err());
+    }
+
+    fn base_test(line: &str, list: &str, expected: Vec<&str>) {
+        let paths = parse_paths(list);
+
+        let actual = select_paths(line, &paths).unwrap();
+
+        assert_eq!(expected, actual);
+    }
+}
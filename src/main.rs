@@ -1,141 +1,880 @@
 mod cli;
+mod config;
 mod cut;
+mod encoding;
+mod escape;
+mod explain;
+mod filter;
+mod json_input;
+mod output;
 mod range_parser;
+mod reader;
+mod splitter;
+mod template;
 
-use std::fs::File;
-use std::io::{self, Read};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::env;
+use std::io::{self, BufWriter, Write};
+use std::panic;
 use std::process::exit;
+use std::thread;
 
-use cut::{cut_line_with_bytes, cut_line_with_characters, cut_line_with_delimiter};
-use range_parser::parse_range;
+use clap::ArgMatches;
+use encoding_rs::Encoding;
+
+use cut::{
+    cut_line_with_bytes, cut_line_with_characters, cut_line_with_splitter, item_limit,
+    resolve_selection, select_bytes, select_items, split_bytes, split_characters,
+};
+use encoding::{parse_encoding, EncodingWriter, InvalidUtf8};
+use escape::unescape;
+use explain::{explain_line, ExplainMode};
+use filter::{parse_filter, Filter};
+use json_input::{parse_paths, select_paths};
+use output::{
+    build_writer, DelimitedWriter, OutputFormat, OutputOptions, OutputWriter, RecordFlags,
+};
+use range_parser::{parse_fixed_range, parse_range_list, Range, RangeList};
+use reader::{
+    decode_records, read_content, remove_carriage_returns, Content, CrlfPolicy, Decompression,
+    FollowReader, RecordSeparator, StreamReader,
+};
+use splitter::{describe, detect_delimiter, parse_widths, read_col_spec, FieldSplitter};
+use template::{parse_template, Template};
+
+// The number of lines used to detect the delimiter with `-d auto`
+static AUTO_DELIMITER_SAMPLE_SIZE: usize = 20;
+static PARALLEL_CHUNK_SIZE: usize = 10_000;
+// 128 + SIGPIPE, the status shells report for a process killed by a closed pipe
+static BROKEN_PIPE_EXIT_CODE: i32 = 141;
 
 fn main() {
-    let args = cli::build_cli().get_matches();
+    let args = match cli::expand_preset(env::args().collect()) {
+        Ok(args) => cli::build_cli().get_matches_from(args),
+        Err(error) => {
+            eprintln!("sbcut: {}", error);
+            exit(1);
+        }
+    };
 
     let default_file = String::from("-");
     let file_path = args.get_one::<String>("FILE").unwrap_or(&default_file);
 
-    let content_result = read_content(file_path, &default_file);
+    let decompression = match args.get_one::<String>("decompress").map(String::as_str) {
+        Some("none") => Decompression::None,
+        _ => Decompression::Auto,
+    };
 
-    if content_result.is_err() {
-        exit(1);
-    }
-    let content = content_result.unwrap();
+    let encoding = get_encoding(&args, "encoding");
+    let output_encoding = get_encoding(&args, "output-encoding");
 
+    let is_literal_delimiter = args.get_flag("literal-delimiter");
     let is_using_nul_as_line_delimiter = args.get_flag("zero_terminated");
 
-    let pattern_to_split = if !is_using_nul_as_line_delimiter {
-        '\n'
+    let default_record_separator = if !is_using_nul_as_line_delimiter {
+        String::from("\n")
     } else {
-        0 as char
+        String::from("\0")
+    };
+    let record_separator = get_delimiter(&args, "record-separator", is_literal_delimiter)
+        .unwrap_or(default_record_separator.clone());
+    let output_record_separator =
+        get_delimiter(&args, "output-record-separator", is_literal_delimiter)
+            .unwrap_or(default_record_separator);
+
+    let invalid_utf8 = args
+        .get_one::<String>("invalid-utf8")
+        .and_then(|name| InvalidUtf8::from_name(name))
+        .unwrap_or(InvalidUtf8::Error);
+    // The invalid bytes are written as they are, so they can't be encoded
+    if invalid_utf8 == InvalidUtf8::Bytes && output_encoding.is_some() {
+        eprintln!("sbcut: --invalid-utf8=bytes can't be used with --output-encoding");
+        exit(1);
+    }
+
+    let is_following = args.get_flag("follow");
+    if is_following && file_path == "-" {
+        eprintln!("sbcut: --follow requires a FILE");
+        exit(1);
+    }
+
+    let output_format = args
+        .get_one::<String>("output-format")
+        .and_then(|name| OutputFormat::from_name(name))
+        .unwrap_or(OutputFormat::Plain);
+    // clap can't require a value of another argument, so the table options are checked here
+    for (option, is_given) in [
+        (
+            "--table-sample",
+            args.get_one::<usize>("table-sample").is_some(),
+        ),
+        ("--table-border", args.get_flag("table-border")),
+    ] {
+        if is_given && output_format != OutputFormat::Table {
+            eprintln!("sbcut: {} requires --output-format=table", option);
+            exit(1);
+        }
+    }
+    // A followed file never ends, so the formats written at the end of the input can't be used
+    match output_format {
+        OutputFormat::Table if is_following && args.get_one::<usize>("table-sample").is_none() => {
+            eprintln!("sbcut: --follow requires --table-sample with --output-format=table");
+            exit(1);
+        }
+        OutputFormat::Html if is_following => {
+            eprintln!("sbcut: --follow can't be used with --output-format=html");
+            exit(1);
+        }
+        _ => {}
+    }
+
+    let is_explaining = args.get_one::<String>("explain").is_some();
+    let is_line_buffered = args.get_flag("line-buffered") || is_following;
+    let threads = args.get_one::<u16>("threads").copied().unwrap_or(1) as usize;
+    let is_parallel = threads > 1
+        && !is_explaining
+        && !is_line_buffered
+        && matches!(
+            output_format,
+            OutputFormat::Plain | OutputFormat::Csv | OutputFormat::Tsv
+        );
+    // The number of records read at a time from the standard input, when it is streamed
+    let batch_size = match is_parallel {
+        true => PARALLEL_CHUNK_SIZE * threads,
+        false => 1,
+    };
+
+    // With --follow, the records already in the file are read first, and the new ones later
+    let mut follow_reader = None;
+    if is_following {
+        match FollowReader::open(
+            file_path,
+            &RecordSeparator::new(&record_separator),
+            decompression,
+            encoding,
+        ) {
+            Ok(reader) => follow_reader = Some(reader),
+            Err(error) => {
+                eprintln!("sbcut: {}", error);
+                exit(1);
+            }
+        }
+    }
+
+    // With -j and --line-buffered, the standard input is cut in batches, as they are read
+    let is_streaming = file_path == "-" && (is_parallel || (is_line_buffered && !is_explaining));
+    let mut stream_reader = None;
+    if is_streaming {
+        match StreamReader::open_stdin(
+            &RecordSeparator::new(&record_separator),
+            decompression,
+            encoding,
+        ) {
+            Ok(reader) => stream_reader = Some(reader),
+            Err(error) => {
+                eprintln!("sbcut: {}", error);
+                exit(1);
+            }
+        }
+    }
+
+    let content = match (&mut follow_reader, &mut stream_reader) {
+        (Some(reader), _) => reader.read_available().map(Content::Buffered),
+        (_, Some(reader)) => reader
+            .read_records(batch_size)
+            .map(|records| Content::Buffered(records.unwrap_or_default())),
+        _ => read_content(file_path, decompression),
+    };
+    let content = match content {
+        Ok(content) => content,
+        Err(error) => {
+            eprintln!("sbcut: {}", error);
+            exit(1);
+        }
+    };
+
+    // A followed file and the streamed standard input are already decoded into UTF-8 by their reader
+    let record_encoding = match (&follow_reader, &stream_reader) {
+        (None, None) => encoding,
+        _ => None,
+    };
+    let (mut lines, invalid_lines) = match decode_records(
+        &content,
+        record_encoding,
+        &RecordSeparator::new(&record_separator),
+        invalid_utf8,
+    ) {
+        Ok(records) => records,
+        Err(error) => {
+            eprintln!("sbcut: {}", error);
+            exit(1);
+        }
     };
+    report_invalid_utf8(&invalid_lines, invalid_utf8, 0);
 
-    let mut lines: Vec<String> = content.split(pattern_to_split).map(String::from).collect();
+    // `auto` is decided once, from the first record, and the records read later are handled the same way
+    let crlf_policy = args
+        .get_one::<String>("crlf")
+        .and_then(|name| CrlfPolicy::from_name(name))
+        .unwrap_or(CrlfPolicy::Auto);
+    let crlf_policy = Cell::new(crlf_policy.resolve(&lines));
+    let carriage_returns = remove_carriage_returns(&mut lines, crlf_policy.get());
+    let mut record_flags = get_record_flags(carriage_returns, &invalid_lines, invalid_utf8);
 
     let actions = (
         args.get_one::<String>("bytes"),
         args.get_one::<String>("characters"),
         args.get_one::<String>("fields"),
+        args.get_one::<String>("format"),
     );
 
+    let no_fields = String::new();
     let cut_information = match actions {
-        (Some(fields), _, _) => (CutType::BYTES, fields),
-        (_, Some(fields), _) => (CutType::CHARACTERS, fields),
-        (_, _, Some(fields)) => (CutType::FIELDS, fields),
+        (Some(fields), _, _, _) => (CutType::BYTES, fields),
+        (_, Some(fields), _, _) => (CutType::CHARACTERS, fields),
+        (_, _, Some(fields), _) => (CutType::FIELDS, fields),
+        (_, _, _, Some(_)) => (CutType::FIELDS, &no_fields),
         _ => unreachable!(),
     };
 
-    let delimiter = args.get_one::<String>("delimiter").unwrap().clone();
+    let delimiter = get_delimiter(&args, "delimiter", is_literal_delimiter).unwrap();
+
+    let splitter = match (
+        args.get_one::<String>("widths"),
+        args.get_one::<String>("col-spec"),
+    ) {
+        (Some(widths), _) => parse_widths(widths),
+        (_, Some(path)) => read_col_spec(path),
+        _ if delimiter == "auto" => {
+            let sample_size = lines.len().min(AUTO_DELIMITER_SAMPLE_SIZE);
+            Ok(detect_delimiter(&lines[..sample_size]))
+        }
+        _ => Ok(FieldSplitter::Delimiter(delimiter.clone())),
+    };
+    let splitter = match splitter {
+        Ok(splitter) => splitter,
+        Err(error) => {
+            eprintln!("sbcut: {}", error);
+            exit(1);
+        }
+    };
+
+    if args.get_flag("verbose") && cut_information.0 == CutType::FIELDS {
+        eprintln!("sbcut: using {}", describe(&splitter));
+    }
 
     let is_showing_complement = args.get_flag("complement");
     let is_showing_only_delimited_lines = args.get_flag("only_delimited");
     let is_showing_non_delimited_lines_in_full = args.get_flag("always_show_no_delimited_lines");
 
-    let output_delimiter = match args.get_one::<String>("output_delimiter") {
-        Some(passed_delimiter) => passed_delimiter.clone(),
-        None => match cut_information {
-            (CutType::FIELDS, _) => delimiter.clone(),
+    let output_delimiter = match get_delimiter(&args, "output_delimiter", is_literal_delimiter) {
+        Some(passed_delimiter) => passed_delimiter,
+        None => match (cut_information.0, &splitter) {
+            (CutType::FIELDS, FieldSplitter::Delimiter(delimiter)) => delimiter.clone(),
+            (CutType::FIELDS, FieldSplitter::SpaceRuns) => String::from(" "),
+            (CutType::FIELDS, FieldSplitter::Widths { .. }) => delimiter.clone(),
             _ => String::from(""),
         },
     };
 
-    cut_lines(
-        &mut lines,
-        cut_information,
-        is_showing_only_delimited_lines,
-        &delimiter,
-        &output_delimiter,
+    let filter = match args
+        .get_one::<String>("where")
+        .map(|input| parse_filter(input))
+    {
+        Some(Ok(filter)) => Some(filter),
+        Some(Err(error)) => {
+            eprintln!("sbcut: {}", error);
+            exit(1);
+        }
+        None => None,
+    };
+
+    let template = match args
+        .get_one::<String>("format")
+        .map(|input| parse_template(input))
+    {
+        Some(Ok(template)) => Some(template),
+        Some(Err(error)) => {
+            eprintln!("sbcut: {}", error);
+            exit(1);
+        }
+        None => None,
+    };
+
+    let explain = match args.get_one::<String>("explain").map(String::as_str) {
+        Some("all") => Some(ExplainMode::All),
+        Some(_) => Some(ExplainMode::First),
+        None => None,
+    };
+    // --explain shows the ranges parsed for each line
+    let fixed_ranges = match explain.is_some() {
+        true => None,
+        false => parse_fixed_range(cut_information.1),
+    };
+
+    let options = CutOptions {
+        cut_type: cut_information.0,
+        // An invalid LIST is reported on each line, in place of the cut items
+        ranges: parse_range_list(cut_information.1),
+        splitter,
+        output_delimiter: output_delimiter.clone(),
         is_showing_complement,
+        is_showing_only_delimited_lines,
         is_showing_non_delimited_lines_in_full,
-    );
+        has_header: args.get_flag("header"),
+        filter,
+        explain,
+        json_paths: match args.get_flag("json") {
+            true => Some(parse_paths(cut_information.1)),
+            false => None,
+        },
+        template,
+        fixed_ranges,
+    };
+
+    let output_options = OutputOptions {
+        format: output_format,
+        output_delimiter: output_delimiter.clone(),
+        output_record_separator,
+        table_sample_size: args.get_one::<usize>("table-sample").copied(),
+        has_table_border: args.get_flag("table-border"),
+    };
+    // All output goes through one locked stdout, flushed when the buffer is full,
+    // or by the writer at the end of each record with --line-buffered and --follow
+    let stdout: Box<dyn Write> = Box::new(BufWriter::new(io::stdout().lock()));
+    let output: Box<dyn Write> = match output_encoding {
+        Some(encoding) => Box::new(EncodingWriter::new(stdout, encoding)),
+        None => stdout,
+    };
+    let separator = RecordSeparator::new(&record_separator);
+    // Decodes the records read after the first ones, numbered after `line_count` lines
+    let decode_batch =
+        |content: &[u8], line_count: usize| -> Result<(Vec<String>, Vec<RecordFlags>), String> {
+            let (mut records, invalid_records) =
+                decode_records(content, record_encoding, &separator, invalid_utf8)?;
+            report_invalid_utf8(&invalid_records, invalid_utf8, line_count);
+            crlf_policy.set(crlf_policy.get().resolve(&records));
+            let carriage_returns = remove_carriage_returns(&mut records, crlf_policy.get());
+            let record_flags = get_record_flags(carriage_returns, &invalid_records, invalid_utf8);
+            let records = records.into_iter().map(Cow::into_owned).collect();
+            Ok((records, record_flags))
+        };
+
+    // Reads the next records of the standard input, if it is streamed
+    let mut next_stream_records = |line_count| match &mut stream_reader {
+        Some(reader) => match reader.read_records(batch_size)? {
+            Some(content) => decode_batch(&content, line_count).map(Some),
+            None => Ok(None),
+        },
+        None => Ok(None),
+    };
+
+    let result = if let Some(mut reader) = follow_reader {
+        let mut next_records = |line_count| {
+            let content = reader.wait_for_records()?;
+            decode_batch(&content, line_count).map(Some)
+        };
+        let mut writer = build_writer(&output_options, output);
+        writer.set_line_buffered(true);
+        stream_lines(
+            &mut lines,
+            &mut record_flags,
+            options,
+            writer.as_mut(),
+            &mut next_records,
+        )
+    } else if is_parallel {
+        cut_lines_in_parallel(
+            &mut lines,
+            &mut record_flags,
+            options,
+            &output_options,
+            output,
+            threads,
+            &mut next_stream_records,
+        )
+    } else if is_streaming {
+        let mut writer = build_writer(&output_options, output);
+        writer.set_line_buffered(true);
+        stream_lines(
+            &mut lines,
+            &mut record_flags,
+            options,
+            writer.as_mut(),
+            &mut next_stream_records,
+        )
+    } else {
+        let mut writer: Box<dyn OutputWriter> = match options.explain {
+            // The explanations are written as plain lines, whatever the output format
+            Some(_) => Box::new(DelimitedWriter::new(output, "", "\n")),
+            None => build_writer(&output_options, output),
+        };
+        writer.set_line_buffered(is_line_buffered);
+        cut_lines(&mut lines, &record_flags, options, writer.as_mut())
+    };
+
+    if let Err(error) = result {
+        eprintln!("sbcut: {}", error);
+        exit(1);
+    }
 }
 
-// TODO - Collect args in a struct
-fn cut_lines(
-    lines: &mut Vec<String>,
-    cut_information: (CutType, &String),
-    is_showing_only_delimited_lines: bool,
-    delimiter: &String,
-    output_delimiter: &str,
+fn get_delimiter(args: &ArgMatches, name: &str, is_literal: bool) -> Option<String> {
+    //! Get a delimiter or separator argument, with its escape sequences replaced unless `is_literal` is set
+    let delimiter = args.get_one::<String>(name)?;
+
+    if is_literal {
+        return Some(delimiter.clone());
+    }
+
+    match unescape(delimiter) {
+        Ok(delimiter) => Some(delimiter),
+        Err(error) => {
+            eprintln!("sbcut: {}", error);
+            exit(1);
+        }
+    }
+}
+
+fn get_encoding(args: &ArgMatches, name: &str) -> Option<&'static Encoding> {
+    //! Get the encoding argument, if it was given
+    let encoding = args.get_one::<String>(name)?;
+
+    match parse_encoding(encoding) {
+        Ok(encoding) => Some(encoding),
+        Err(error) => {
+            eprintln!("sbcut: {}", error);
+            exit(1);
+        }
+    }
+}
+
+// Reads and decodes the next records of a followed file or a streamed input, numbered after the given number of lines,
+// with the flags of their output records. Returns `None` at the end of the input.
+type NextRecords<'a> =
+    dyn FnMut(usize) -> Result<Option<(Vec<String>, Vec<RecordFlags>)>, String> + 'a;
+
+/// Struct that collects everything needed to cut the lines
+struct CutOptions {
+    cut_type: CutType,
+    ranges: Result<RangeList, String>,
+    splitter: FieldSplitter,
+    output_delimiter: String,
     is_showing_complement: bool,
+    is_showing_only_delimited_lines: bool,
     is_showing_non_delimited_lines_in_full: bool,
-) {
+    has_header: bool,
+    filter: Option<Filter>,
+    explain: Option<ExplainMode>,
+    json_paths: Option<Vec<Vec<String>>>,
+    template: Option<Template>,
+    // The ranges, if they are the same for all lines
+    fixed_ranges: Option<Vec<Range>>,
+}
+
+fn cut_lines(
+    lines: &mut Vec<Cow<str>>,
+    record_flags: &[RecordFlags],
+    mut options: CutOptions,
+    writer: &mut dyn OutputWriter,
+) -> Result<(), String> {
+    remove_last_empty_record(lines);
+    prepare_lines(lines, &mut options)?;
+    cut_chunk(lines, record_flags, 0, &options, writer)?;
+
+    writer.finish().map_err(output_error)
+}
+
+fn cut_lines_in_parallel(
+    lines: &mut Vec<Cow<str>>,
+    record_flags: &mut Vec<RecordFlags>,
+    mut options: CutOptions,
+    output_options: &OutputOptions,
+    mut output: Box<dyn Write>,
+    threads: usize,
+    next_records: &mut NextRecords,
+) -> Result<(), String> {
+    //! Cut the lines like `cut_lines`, in chunks of `PARALLEL_CHUNK_SIZE` lines, `threads` chunks at a time.
+    //! Each chunk is cut into its own buffer, and the buffers are written in the original order
+    //! as soon as all chunks of the batch are done. A streamed input is cut in batches as it is read,
+    //! so the output starts before the whole input is read.
+    let batch_size = PARALLEL_CHUNK_SIZE * threads;
+    let mut line_count = 0;
+    let mut records = std::mem::take(lines);
+    let mut batch_record_flags = std::mem::take(record_flags);
+
+    remove_last_empty_record(&mut records);
+    prepare_lines(&records, &mut options)?;
+    let options = &options;
+
+    loop {
+        for (batch_index, batch) in records.chunks(batch_size).enumerate() {
+            let first_line_number = line_count + batch_index * batch_size;
+            let batch_flags = batch_record_flags
+                .get(batch_index * batch_size..)
+                .unwrap_or_default();
+            let buffers = cut_batch(
+                batch,
+                batch_flags,
+                first_line_number,
+                options,
+                output_options,
+            );
+
+            for buffer in buffers {
+                output.write_all(&buffer?).map_err(output_error)?;
+            }
+        }
+        line_count += records.len();
+
+        match next_records(line_count)? {
+            Some((next, next_record_flags)) => {
+                records = next.into_iter().map(Cow::Owned).collect();
+                batch_record_flags = next_record_flags;
+                remove_last_empty_record(&mut records);
+            }
+            None => break,
+        }
+    }
+
+    output.flush().map_err(output_error)
+}
+
+fn cut_batch(
+    batch: &[Cow<str>],
+    record_flags: &[RecordFlags],
+    first_line_number: usize,
+    options: &CutOptions,
+    output_options: &OutputOptions,
+) -> Vec<Result<Vec<u8>, String>> {
+    //! Cut each chunk of `PARALLEL_CHUNK_SIZE` lines of the batch in its own thread, into its own buffer.
+    //! A panic in a thread is resumed in the calling thread, with its own message.
+    thread::scope(|scope| {
+        let handles: Vec<_> = batch
+            .chunks(PARALLEL_CHUNK_SIZE)
+            .enumerate()
+            .map(|(chunk_index, chunk)| {
+                let chunk_record_flags = record_flags
+                    .get(chunk_index * PARALLEL_CHUNK_SIZE..)
+                    .unwrap_or_default();
+                let chunk_first_line_number = first_line_number + chunk_index * PARALLEL_CHUNK_SIZE;
+                scope.spawn(move || {
+                    let mut buffer = vec![];
+                    let mut writer = build_writer(output_options, Box::new(&mut buffer));
+                    cut_chunk(
+                        chunk,
+                        chunk_record_flags,
+                        chunk_first_line_number,
+                        options,
+                        writer.as_mut(),
+                    )?;
+                    drop(writer);
+                    Ok(buffer)
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| panic::resume_unwind(panic))
+            })
+            .collect()
+    })
+}
+
+fn stream_lines(
+    lines: &mut Vec<Cow<str>>,
+    record_flags: &mut Vec<RecordFlags>,
+    mut options: CutOptions,
+    writer: &mut dyn OutputWriter,
+    next_records: &mut NextRecords,
+) -> Result<(), String> {
+    //! Cut the lines read so far, then keep cutting the next records of a followed file or of the standard input,
+    //! as they arrive. The column names are resolved from the first line, once there is one.
+    //! Only returns on an error, or at the end of the input, which a followed file never reaches.
+    let mut line_count = 0;
+    let mut is_prepared = false;
+    let mut batch = std::mem::take(lines);
+    let mut batch_record_flags = std::mem::take(record_flags);
+
+    loop {
+        // Each batch ends with a separator, so its last record is empty
+        remove_last_empty_record(&mut batch);
+        if !is_prepared && !batch.is_empty() {
+            prepare_lines(&batch, &mut options)?;
+            is_prepared = true;
+        }
+
+        cut_chunk(&batch, &batch_record_flags, line_count, &options, writer)?;
+        line_count += batch.len();
+
+        match next_records(line_count)? {
+            Some((records, next_record_flags)) => {
+                batch = records.into_iter().map(Cow::Owned).collect();
+                batch_record_flags = next_record_flags;
+            }
+            None => return writer.finish().map_err(output_error),
+        }
+    }
+}
+
+fn remove_last_empty_record(lines: &mut Vec<Cow<str>>) {
+    //! Remove the empty record after the last separator
     if lines.last().is_some_and(|line| line.is_empty()) {
         lines.pop();
     };
+}
+
+fn prepare_lines(lines: &[Cow<str>], options: &mut CutOptions) -> Result<(), String> {
+    //! Resolve the column names from the header. Without any line, there is nothing to resolve them against.
+    if options.has_header {
+        if let Some(header) = lines.first() {
+            let columns: Vec<&str> = options.splitter.split(header);
+            if let Some(filter) = &mut options.filter {
+                filter.resolve_names(&columns)?;
+            }
+            if let Some(template) = &mut options.template {
+                template.resolve_names(&columns)?;
+            }
+        }
+    } else if options
+        .filter
+        .as_ref()
+        .is_some_and(|filter| filter.has_names())
+    {
+        return Err(String::from("Column names in --where require --header"));
+    } else if options
+        .template
+        .as_ref()
+        .is_some_and(|template| template.has_names())
+    {
+        return Err(String::from("Column names in --format require --header"));
+    }
+
+    Ok(())
+}
+
+fn cut_chunk(
+    lines: &[Cow<str>],
+    record_flags: &[RecordFlags],
+    first_line_number: usize,
+    options: &CutOptions,
+    writer: &mut dyn OutputWriter,
+) -> Result<(), String> {
+    //! Cut the lines of a chunk that starts at `first_line_number`, without finishing the writer.
+    //! The output record of each line is written according to its `record_flags`.
+    let cut_type = options.cut_type;
+    let splitter = &options.splitter;
+    // The number of items of the last resolved selection, and the indexes selected for each output slot
+    let mut selection: (Option<usize>, Vec<Vec<usize>>) = (None, vec![]);
 
-    for line in lines {
-        let (cut_type, fields) = cut_information;
+    for (index, line) in lines.iter().enumerate() {
+        let line: &str = line;
+        let line_number = first_line_number + index;
+        let is_header = options.has_header && line_number == 0;
+        let flags = record_flags.get(index).copied().unwrap_or_default();
+        writer.set_record_flags(flags);
+
+        if let (false, Some(filter)) = (is_header, &options.filter) {
+            let fields: Vec<&str> = splitter.split(line);
+            if !filter.matches(&fields) {
+                continue;
+            }
+        }
+
+        if let Some(paths) = &options.json_paths {
+            match select_paths(line, paths) {
+                Ok(items) => {
+                    let items: Vec<&str> = items.iter().map(String::as_str).collect();
+                    write_items(writer, &items, is_header)?
+                }
+                Err(error) => eprintln!("sbcut: line {}: {}", line_number + 1, error),
+            }
+            continue;
+        }
 
         // TODO - This can be improved
-        if cut_type == CutType::FIELDS && !line.contains(delimiter) {
-            if is_showing_only_delimited_lines {
+        if cut_type == CutType::FIELDS && !splitter.is_delimited(line) {
+            if options.is_showing_only_delimited_lines {
                 continue;
-            } else if is_showing_non_delimited_lines_in_full {
-                println!("{}", line);
+            } else if options.is_showing_non_delimited_lines_in_full {
+                write_items(writer, &[line], is_header)?;
                 continue;
             }
         }
 
-        let n = match cut_information {
-            (CutType::FIELDS, _) => line.split(delimiter).count(),
+        if let Some(template) = &options.template {
+            let fields: Vec<&str> = splitter.split(line);
+            let output = template.render(&fields, &options.output_delimiter);
+            write_items(writer, &[&output], is_header)?;
+            continue;
+        }
+
+        // With fixed ranges, only the fields up to the highest needed index are split
+        let limit = match &options.fixed_ranges {
+            Some(ranges) => item_limit(ranges, options.is_showing_complement),
+            None => usize::MAX,
+        };
+        // The fields, or the bytes of a line with invalid bytes kept by --invalid-utf8=bytes
+        let fields: Vec<&str> = match cut_type {
+            CutType::FIELDS => splitter.split_first(line, limit),
+            CutType::BYTES if flags.has_raw_bytes => split_bytes(line),
+            _ => vec![],
+        };
+        let n = match cut_type {
+            CutType::FIELDS => fields.len(),
+            CutType::BYTES if !fields.is_empty() => fields.len(),
             _ => line.len(),
         };
-        // let n = line.split(delimiter).count();
-        let ranges = parse_range(fields, n);
+        let find_match = |pattern: &regex::Regex| match cut_type {
+            CutType::FIELDS => fields.iter().position(|field| pattern.is_match(field)),
+            CutType::CHARACTERS => pattern
+                .find(line)
+                .map(|found| line[..found.start()].chars().count()),
+            CutType::BYTES if !fields.is_empty() => pattern
+                .find(line)
+                .map(|found| split_bytes(&line[..found.start()]).len()),
+            CutType::BYTES => pattern.find(line).map(|found| found.start()),
+        };
+        let ranges: Result<Cow<[Range]>, String> = match &options.fixed_ranges {
+            Some(ranges) => Ok(Cow::Borrowed(ranges)),
+            None => match &options.ranges {
+                Ok(ranges) => Ok(Cow::Owned(ranges.resolve(n, &find_match))),
+                Err(error) => Err(error.clone()),
+            },
+        };
 
-        let output = match ranges {
-            Ok(ranges) => cut_line(
-                cut_type,
-                ranges,
-                line,
-                delimiter,
-                output_delimiter,
-                is_showing_complement,
-            ),
-            Err(error) => error,
+        let items = match &ranges {
+            Ok(ranges) => {
+                // Fixed ranges select the same indexes from all lines with the same number of items
+                let is_cached = options.fixed_ranges.is_some() && selection.0 == Some(n);
+                if !is_cached {
+                    let slots = resolve_slots(ranges, n as i32, options.is_showing_complement);
+                    selection = (Some(n), slots);
+                }
+                cut_line(cut_type, &selection.1, line, &fields, limit)
+            }
+            Err(error) => vec![error.as_str()],
         };
-        println!("{}", output);
+
+        if let Some(explain_mode) = options.explain {
+            let all_items = match flags.has_raw_bytes {
+                true => fields.clone(),
+                false => get_items(cut_type, line, splitter, n),
+            };
+            let explanation = explain_line(
+                line_number + 1,
+                line,
+                &all_items,
+                n as i32,
+                &ranges.clone().map(Cow::into_owned),
+                options.is_showing_complement,
+                &items.join(&options.output_delimiter),
+            );
+            write_items(writer, &[&explanation], false)?;
+
+            if explain_mode == ExplainMode::First {
+                break;
+            }
+            continue;
+        }
+
+        write_items(writer, &items, is_header)?;
     }
+
+    Ok(())
 }
 
-fn read_content(file_path: &String, default_file: &String) -> Result<String, String> {
-    let mut buffer = String::new();
-    if *file_path == *default_file {
-        let mut buffer = String::new();
-        match io::stdin().read_to_string(&mut buffer) {
-            Ok(_) => Ok(buffer),
-            Err(_) => Err(String::from("Can't read input file")),
-        }
+fn write_items(
+    writer: &mut dyn OutputWriter,
+    items: &[&str],
+    is_header: bool,
+) -> Result<(), String> {
+    let result = if is_header {
+        writer.write_header(items)
     } else {
-        // Read from the file
-        let file = File::open(file_path).unwrap();
-        let mut reader = io::BufReader::new(file);
-        match reader.read_to_string(&mut buffer) {
-            Ok(_) => Ok(buffer),
-            Err(_) => Err(String::from("Error when reading from stdin")),
+        writer.write_record(items)
+    };
+
+    result.map_err(output_error)
+}
+
+fn report_invalid_utf8(
+    invalid_lines: &[usize],
+    invalid_utf8: InvalidUtf8,
+    first_line_number: usize,
+) {
+    //! Report the lines with invalid UTF-8 on stderr, numbered from `first_line_number`
+    if invalid_lines.is_empty() {
+        return;
+    }
+
+    if invalid_utf8 == InvalidUtf8::SkipLine {
+        for line_number in invalid_lines {
+            eprintln!(
+                "sbcut: line {}: invalid UTF-8, skipped",
+                first_line_number + line_number
+            );
+        }
+    }
+    eprintln!("sbcut: {} lines with invalid UTF-8", invalid_lines.len());
+}
+
+fn get_record_flags(
+    carriage_returns: Vec<bool>,
+    invalid_records: &[usize],
+    invalid_utf8: InvalidUtf8,
+) -> Vec<RecordFlags> {
+    //! Get the flags of the output records, from whether the records end with a carriage return to restore,
+    //! and from the numbers of the records with invalid UTF-8, whose bytes are kept by `--invalid-utf8=bytes`
+    let mut record_flags: Vec<RecordFlags> = carriage_returns
+        .into_iter()
+        .map(|has_carriage_return| RecordFlags {
+            has_carriage_return,
+            has_raw_bytes: false,
+        })
+        .collect();
+
+    if invalid_utf8 == InvalidUtf8::Bytes {
+        for record_number in invalid_records {
+            if let Some(flags) = record_flags.get_mut(record_number - 1) {
+                flags.has_raw_bytes = true;
+            }
         }
     }
+    record_flags
+}
+
+fn output_error(error: io::Error) -> String {
+    //! Describe an error while writing the output.
+    //!
+    //! A closed pipe, as in `sbcut ... | head`, is not an error. sbcut exits quietly,
+    //! with the status of a process killed by SIGPIPE, like coreutils cut.
+    if error.kind() == io::ErrorKind::BrokenPipe {
+        exit(BROKEN_PIPE_EXIT_CODE);
+    }
+
+    format!("Can't write output: {}", error)
+}
+
+fn get_items<'a>(
+    cut_type: CutType,
+    line: &'a str,
+    splitter: &FieldSplitter,
+    n: usize,
+) -> Vec<&'a str> {
+    //! Get all the items of the line, as seen by the cut functions
+    let whole_line = Range::new(0, n as i32, 1);
+
+    match cut_type {
+        CutType::BYTES => cut_line_with_bytes(line, whole_line, false),
+        CutType::CHARACTERS => cut_line_with_characters(line, whole_line, false),
+        CutType::FIELDS => cut_line_with_splitter(line, whole_line, splitter, false),
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, PartialOrd)]
@@ -145,32 +884,48 @@ pub enum CutType {
     FIELDS,
 }
 
-fn cut_line(
-    cut_type: CutType,
-    ranges: Vec<range_parser::Range>,
-    line: &str,
-    delimiter: &str,
-    output_delimiter: &str,
-    is_showing_complement: bool,
-) -> String {
-    let ranges_iter = ranges.iter();
-
-    let items: Vec<String> = match cut_type {
-        CutType::BYTES => ranges_iter
-            .map(|range| cut_line_with_bytes(line, *range, is_showing_complement))
-            .map(|items| items.join(output_delimiter))
-            .collect(),
-        CutType::CHARACTERS => ranges_iter
-            .map(|range| cut_line_with_characters(line, *range, is_showing_complement))
-            .map(|items| items.join(output_delimiter))
-            .collect(),
-        CutType::FIELDS => ranges_iter
-            .map(|range| {
-                cut_line_with_delimiter(line, *range, delimiter.to_owned(), is_showing_complement)
-            })
-            .map(|items| items.join(output_delimiter))
+fn resolve_slots(ranges: &[Range], n: i32, is_showing_complement: bool) -> Vec<Vec<usize>> {
+    //! Return the indexes selected for each slot of the output.
+    //! Each range is a slot, except with the complement, where the ranges are merged into one.
+    match is_showing_complement {
+        true => vec![resolve_selection(ranges, n, true)],
+        false => ranges
+            .iter()
+            .map(|range| resolve_selection(&[*range], n, false))
             .collect(),
+    }
+}
+
+fn cut_line<'a>(
+    cut_type: CutType,
+    slots: &[Vec<usize>],
+    line: &'a str,
+    fields: &[&'a str],
+    limit: usize,
+) -> Vec<&'a str> {
+    //! Return the items of the line at the indexes of each slot.
+    //! A slot which selects nothing leaves an empty item, so `-f 5,0` on a shorter line still outputs `,` before the first field.
+    //! The fields are split once, and only the first `limit` characters are split.
+    //! The bytes of a line with invalid bytes are split into `fields` too, as `split_bytes` does.
+    let characters = match cut_type {
+        CutType::CHARACTERS => split_characters(line, limit),
+        _ => vec![],
     };
 
-    items.join(output_delimiter)
+    let mut items = vec![];
+    for indexes in slots {
+        let selected = match cut_type {
+            CutType::BYTES if !fields.is_empty() => select_items(fields, indexes),
+            CutType::BYTES => select_bytes(line, indexes),
+            CutType::CHARACTERS => select_items(&characters, indexes),
+            CutType::FIELDS => select_items(fields, indexes),
+        };
+
+        match selected.is_empty() {
+            true => items.push(""),
+            false => items.extend(selected),
+        }
+    }
+
+    items
 }
@@ -0,0 +1,1105 @@
+//! Module, containing the output writers
+//!
+//! Each cut line is passed to an `OutputWriter` as the list of selected items.
+//! The writer decides how the items are formatted:
+//!
+//! `DelimitedWriter` - join the items with the output delimiter (the default)
+//!
+//! `JsonLinesWriter` - write each line as a JSON array, or as an object keyed by the header in header mode
+//!
+//! `CsvWriter` - join the items with `,`, quoting them as described in RFC 4180
+//!
+//! `TsvWriter` - join the items with tabs, escaping tabs, newlines and backslashes
+//!
+//! `TableWriter` - align the items in columns, optionally with borders
+//!
+//! `MarkdownWriter` - write a Markdown table, escaping pipes
+//!
+//! `HtmlWriter` - write an HTML table, escaping the HTML special characters
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::encoding::restore_raw_bytes;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OutputFormat {
+    Plain,
+    JsonLines,
+    Csv,
+    Tsv,
+    Table,
+    Markdown,
+    Html,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        //! Get the output format from its name, as given to `--output-format`
+        match name {
+            "plain" => Some(OutputFormat::Plain),
+            "jsonl" => Some(OutputFormat::JsonLines),
+            "csv" => Some(OutputFormat::Csv),
+            "tsv" => Some(OutputFormat::Tsv),
+            "table" => Some(OutputFormat::Table),
+            "markdown" => Some(OutputFormat::Markdown),
+            "html" => Some(OutputFormat::Html),
+            _ => None,
+        }
+    }
+}
+
+/// Trait for writing the selected items of each line
+pub trait OutputWriter {
+    fn write_record(&mut self, items: &[&str]) -> io::Result<()>;
+
+    fn write_header(&mut self, items: &[&str]) -> io::Result<()> {
+        //! Write the selected items of the header line. By default, it's written as any other line.
+        self.write_record(items)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        //! Called after the last line, for writers that need to buffer the output
+        Ok(())
+    }
+
+    /// The end of the records written by the writer
+    fn record_end(&mut self) -> &mut RecordEnd;
+
+    fn set_record_flags(&mut self, flags: RecordFlags) {
+        //! Set how the next records are written, from how their input records were read
+        self.record_end().flags = flags;
+    }
+
+    fn set_line_buffered(&mut self, is_line_buffered: bool) {
+        //! Set whether the output is flushed at the end of each record, with `--line-buffered` and `--follow`
+        self.record_end().is_line_buffered = is_line_buffered;
+    }
+}
+
+/// What the output record of a line depends on, from how its input record was read
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RecordFlags {
+    // The input record ended with a carriage return, which `--crlf` keeps
+    pub has_carriage_return: bool,
+    // The input record had invalid bytes, which `--invalid-utf8=bytes` keeps as they are
+    pub has_raw_bytes: bool,
+}
+
+/// The end of the output records, shared by all writers.
+/// With `--crlf=keep`, the records read with a carriage return end with one too.
+pub struct RecordEnd {
+    separator: String,
+    flags: RecordFlags,
+    is_line_buffered: bool,
+}
+
+impl RecordEnd {
+    fn new(separator: &str) -> Self {
+        RecordEnd {
+            separator: separator.to_owned(),
+            flags: RecordFlags::default(),
+            is_line_buffered: false,
+        }
+    }
+
+    fn write_content(&self, output: &mut impl Write, content: &str) -> io::Result<()> {
+        //! Write the content of the current record.
+        //! The invalid bytes of a record read with `--invalid-utf8=bytes` are written as they were read.
+        match self.flags.has_raw_bytes {
+            true => output.write_all(&restore_raw_bytes(content)),
+            false => output.write_all(content.as_bytes()),
+        }
+    }
+
+    fn write(&self, output: &mut impl Write) -> io::Result<()> {
+        //! End the current record, or a line of the table around it, and flush it if the output is line buffered
+        if self.flags.has_carriage_return {
+            output.write_all(b"\r")?;
+        }
+        output.write_all(self.separator.as_bytes())?;
+
+        match self.is_line_buffered {
+            true => output.flush(),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Struct that collects everything needed to create an output writer
+pub struct OutputOptions {
+    pub format: OutputFormat,
+    pub output_delimiter: String,
+    pub output_record_separator: String,
+    pub table_sample_size: Option<usize>,
+    pub has_table_border: bool,
+}
+
+pub fn build_writer<'a>(
+    options: &OutputOptions,
+    output: Box<dyn Write + 'a>,
+) -> Box<dyn OutputWriter + 'a> {
+    //! Create the writer for the given output format
+    match options.format {
+        OutputFormat::Plain => Box::new(DelimitedWriter::new(
+            output,
+            &options.output_delimiter,
+            &options.output_record_separator,
+        )),
+        OutputFormat::JsonLines => Box::new(JsonLinesWriter::new(output)),
+        OutputFormat::Csv => Box::new(CsvWriter::new(output)),
+        OutputFormat::Tsv => Box::new(TsvWriter::new(output)),
+        OutputFormat::Table => Box::new(TableWriter::new(
+            output,
+            options.table_sample_size,
+            options.has_table_border,
+        )),
+        OutputFormat::Markdown => Box::new(MarkdownWriter::new(output)),
+        OutputFormat::Html => Box::new(HtmlWriter::new(output)),
+    }
+}
+
+/// Writer that joins the items with the output delimiter, and ends each line with the record separator
+pub struct DelimitedWriter<W: Write> {
+    output: W,
+    delimiter: String,
+    record_end: RecordEnd,
+}
+
+impl<W: Write> DelimitedWriter<W> {
+    pub fn new(output: W, delimiter: &str, record_separator: &str) -> Self {
+        DelimitedWriter {
+            output,
+            delimiter: delimiter.to_owned(),
+            record_end: RecordEnd::new(record_separator),
+        }
+    }
+}
+
+impl<W: Write> OutputWriter for DelimitedWriter<W> {
+    fn write_record(&mut self, items: &[&str]) -> io::Result<()> {
+        for (index, item) in items.iter().enumerate() {
+            if index > 0 {
+                self.output.write_all(self.delimiter.as_bytes())?;
+            }
+            self.record_end.write_content(&mut self.output, item)?;
+        }
+        self.record_end.write(&mut self.output)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+
+    fn record_end(&mut self) -> &mut RecordEnd {
+        &mut self.record_end
+    }
+}
+
+/// Writer that outputs each line as a JSON value on its own line
+///
+/// If a header is written, the following lines are written as objects, keyed by the header items.
+pub struct JsonLinesWriter<W: Write> {
+    output: W,
+    record_end: RecordEnd,
+    keys: Option<Vec<String>>,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    pub fn new(output: W) -> Self {
+        JsonLinesWriter {
+            output,
+            record_end: RecordEnd::new("\n"),
+            keys: None,
+        }
+    }
+}
+
+impl<W: Write> OutputWriter for JsonLinesWriter<W> {
+    fn write_record(&mut self, items: &[&str]) -> io::Result<()> {
+        let values: Vec<String> = items.iter().map(|item| to_json_string(item)).collect();
+
+        match &self.keys {
+            Some(keys) => {
+                // Items without a header are keyed by their position
+                let keys: Cow<[String]> = if values.len() > keys.len() {
+                    let positions = (keys.len()..values.len()).map(|index| index.to_string());
+                    Cow::Owned(unique_keys(keys.iter().cloned().chain(positions)))
+                } else {
+                    Cow::Borrowed(keys)
+                };
+                let members: Vec<String> = keys
+                    .iter()
+                    .zip(&values)
+                    .map(|(key, value)| format!("{}:{}", to_json_string(key), value))
+                    .collect();
+                let object = format!("{{{}}}", members.join(","));
+                self.record_end.write_content(&mut self.output, &object)?;
+            }
+            None => {
+                let array = format!("[{}]", values.join(","));
+                self.record_end.write_content(&mut self.output, &array)?;
+            }
+        }
+        self.record_end.write(&mut self.output)
+    }
+
+    fn write_header(&mut self, items: &[&str]) -> io::Result<()> {
+        self.keys = Some(unique_keys(items.iter().map(|item| item.to_string())));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+
+    fn record_end(&mut self) -> &mut RecordEnd {
+        &mut self.record_end
+    }
+}
+
+fn unique_keys(keys: impl Iterator<Item = String>) -> Vec<String> {
+    //! Make the keys of an object unique, e.g. when a field is selected twice.
+    //! A repeated key is suffixed with `_2`, `_3` and so on.
+    let mut used_keys = HashSet::new();
+    keys.map(|key| {
+        let mut unique_key = key.clone();
+        let mut count = 1;
+        while !used_keys.insert(unique_key.clone()) {
+            count += 1;
+            unique_key = format!("{}_{}", key, count);
+        }
+        unique_key
+    })
+    .collect()
+}
+
+fn to_json_string(value: &str) -> String {
+    //! Quote and escape a string as a JSON string
+    serde_json::Value::from(value).to_string()
+}
+
+/// Writer that outputs each line as a CSV record
+pub struct CsvWriter<W: Write> {
+    output: W,
+    record_end: RecordEnd,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(output: W) -> Self {
+        CsvWriter {
+            output,
+            record_end: RecordEnd::new("\n"),
+        }
+    }
+}
+
+impl<W: Write> OutputWriter for CsvWriter<W> {
+    fn write_record(&mut self, items: &[&str]) -> io::Result<()> {
+        let fields: Vec<String> = items.iter().map(|item| to_csv_field(item)).collect();
+        self.record_end
+            .write_content(&mut self.output, &fields.join(","))?;
+        self.record_end.write(&mut self.output)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+
+    fn record_end(&mut self) -> &mut RecordEnd {
+        &mut self.record_end
+    }
+}
+
+fn to_csv_field(value: &str) -> String {
+    //! Quote the field if it contains a comma, a quote or a line break. Quotes are escaped by doubling them.
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+/// Writer that outputs each line as a TSV record
+pub struct TsvWriter<W: Write> {
+    output: W,
+    record_end: RecordEnd,
+}
+
+impl<W: Write> TsvWriter<W> {
+    pub fn new(output: W) -> Self {
+        TsvWriter {
+            output,
+            record_end: RecordEnd::new("\n"),
+        }
+    }
+}
+
+impl<W: Write> OutputWriter for TsvWriter<W> {
+    fn write_record(&mut self, items: &[&str]) -> io::Result<()> {
+        let fields: Vec<String> = items.iter().map(|item| to_tsv_field(item)).collect();
+        self.record_end
+            .write_content(&mut self.output, &fields.join("\t"))?;
+        self.record_end.write(&mut self.output)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+
+    fn record_end(&mut self) -> &mut RecordEnd {
+        &mut self.record_end
+    }
+}
+
+fn to_tsv_field(value: &str) -> String {
+    //! Escape backslashes, tabs and line breaks with a backslash
+    let mut field = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            '\\' => field.push_str("\\\\"),
+            '\t' => field.push_str("\\t"),
+            '\n' => field.push_str("\\n"),
+            '\r' => field.push_str("\\r"),
+            _ => field.push(character),
+        }
+    }
+    field
+}
+
+/// Writer that aligns the items in columns
+///
+/// The column widths are calculated from the first `sample_size` lines, or from all lines if it is `None`.
+/// Until then, the lines are kept in memory. Items wider than their column are not cut.
+pub struct TableWriter<W: Write> {
+    output: W,
+    record_end: RecordEnd,
+    sample_size: Option<usize>,
+    has_border: bool,
+    // The rows kept until the widths are known, with whether they are headers and how they are written
+    buffered_rows: Vec<(Vec<String>, bool, RecordFlags)>,
+    widths: Option<Vec<usize>>,
+}
+
+impl<W: Write> TableWriter<W> {
+    pub fn new(output: W, sample_size: Option<usize>, has_border: bool) -> Self {
+        TableWriter {
+            output,
+            record_end: RecordEnd::new("\n"),
+            sample_size,
+            has_border,
+            buffered_rows: vec![],
+            widths: None,
+        }
+    }
+
+    fn flush_buffered_rows(&mut self) -> io::Result<()> {
+        //! Calculate the column widths from the buffered rows and write them
+        let rows = std::mem::take(&mut self.buffered_rows);
+
+        let mut widths: Vec<usize> = vec![];
+        for (row, _, _) in &rows {
+            for (index, item) in row.iter().enumerate() {
+                if index == widths.len() {
+                    widths.push(0);
+                }
+                widths[index] = widths[index].max(item.width());
+            }
+        }
+        self.widths = Some(widths);
+
+        if self.has_border {
+            // The top border ends like the first row
+            if let Some((_, _, flags)) = rows.first() {
+                self.record_end.flags = *flags;
+            }
+            self.write_border('┌', '┬', '┐')?;
+        }
+
+        for (row, is_header, flags) in rows {
+            let row: Vec<&str> = row.iter().map(String::as_str).collect();
+            self.record_end.flags = flags;
+            self.write_row(&row, is_header)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_row(&mut self, items: &[&str], is_header: bool) -> io::Result<()> {
+        let widths = self.widths.clone().unwrap_or_default();
+        let column_count = widths.len().max(items.len());
+
+        let cells: Vec<String> = (0..column_count)
+            .map(|index| {
+                let item = items.get(index).copied().unwrap_or("");
+                let width = widths.get(index).copied().unwrap_or(0);
+                let is_last = index + 1 == column_count;
+
+                if is_last && !self.has_border {
+                    // Don't leave trailing whitespace
+                    item.to_owned()
+                } else {
+                    format!("{}{}", item, " ".repeat(width.saturating_sub(item.width())))
+                }
+            })
+            .collect();
+
+        if self.has_border {
+            let row = format!("│ {} │", cells.join(" │ "));
+            self.record_end.write_content(&mut self.output, &row)?;
+            self.record_end.write(&mut self.output)?;
+        } else {
+            self.record_end
+                .write_content(&mut self.output, &cells.join("  "))?;
+            self.record_end.write(&mut self.output)?;
+        }
+
+        if is_header {
+            if self.has_border {
+                self.write_border('├', '┼', '┤')?;
+            } else {
+                let separators: Vec<String> =
+                    widths.iter().map(|width| "-".repeat(*width)).collect();
+                write!(self.output, "{}", separators.join("  "))?;
+                self.record_end.write(&mut self.output)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_border(&mut self, left: char, middle: char, right: char) -> io::Result<()> {
+        let widths = self.widths.clone().unwrap_or_default();
+        if widths.is_empty() {
+            return Ok(());
+        }
+
+        let segments: Vec<String> = widths.iter().map(|width| "─".repeat(width + 2)).collect();
+        write!(
+            self.output,
+            "{}{}{}",
+            left,
+            segments.join(&middle.to_string()),
+            right
+        )?;
+        self.record_end.write(&mut self.output)
+    }
+
+    fn add_row(&mut self, items: &[&str], is_header: bool) -> io::Result<()> {
+        if self.widths.is_some() {
+            return self.write_row(items, is_header);
+        }
+
+        let row = items.iter().map(|item| item.to_string()).collect();
+        self.buffered_rows
+            .push((row, is_header, self.record_end.flags));
+        if self
+            .sample_size
+            .is_some_and(|sample_size| self.buffered_rows.len() >= sample_size)
+        {
+            self.flush_buffered_rows()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> OutputWriter for TableWriter<W> {
+    fn write_record(&mut self, items: &[&str]) -> io::Result<()> {
+        self.add_row(items, false)
+    }
+
+    fn write_header(&mut self, items: &[&str]) -> io::Result<()> {
+        self.add_row(items, true)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if self.widths.is_none() {
+            self.flush_buffered_rows()?;
+        }
+        if self.has_border {
+            self.write_border('└', '┴', '┘')?;
+        }
+        self.output.flush()
+    }
+
+    fn record_end(&mut self) -> &mut RecordEnd {
+        &mut self.record_end
+    }
+}
+
+/// Writer that outputs a Markdown table
+///
+/// Markdown tables always have a header. If no header is written, an empty one is used.
+pub struct MarkdownWriter<W: Write> {
+    output: W,
+    record_end: RecordEnd,
+    has_started: bool,
+}
+
+impl<W: Write> MarkdownWriter<W> {
+    pub fn new(output: W) -> Self {
+        MarkdownWriter {
+            output,
+            record_end: RecordEnd::new("\n"),
+            has_started: false,
+        }
+    }
+
+    fn write_row(&mut self, items: &[&str]) -> io::Result<()> {
+        let cells: Vec<String> = items.iter().map(|item| to_markdown_cell(item)).collect();
+        let row = format!("| {} |", cells.join(" | "));
+        self.record_end.write_content(&mut self.output, &row)?;
+        self.record_end.write(&mut self.output)
+    }
+
+    fn write_separator(&mut self, column_count: usize) -> io::Result<()> {
+        write!(self.output, "|{}", " --- |".repeat(column_count))?;
+        self.record_end.write(&mut self.output)
+    }
+}
+
+impl<W: Write> OutputWriter for MarkdownWriter<W> {
+    fn write_record(&mut self, items: &[&str]) -> io::Result<()> {
+        if !self.has_started {
+            self.write_header(&vec![""; items.len()])?;
+        }
+        self.write_row(items)
+    }
+
+    fn write_header(&mut self, items: &[&str]) -> io::Result<()> {
+        self.has_started = true;
+        self.write_row(items)?;
+        self.write_separator(items.len())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+
+    fn record_end(&mut self) -> &mut RecordEnd {
+        &mut self.record_end
+    }
+}
+
+fn to_markdown_cell(value: &str) -> String {
+    //! Escape the pipes and replace the line breaks, which would end the table row
+    value
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\n', '\r'], "<br>")
+}
+
+/// Writer that outputs an HTML table
+pub struct HtmlWriter<W: Write> {
+    output: W,
+    record_end: RecordEnd,
+    has_started: bool,
+}
+
+impl<W: Write> HtmlWriter<W> {
+    pub fn new(output: W) -> Self {
+        HtmlWriter {
+            output,
+            record_end: RecordEnd::new("\n"),
+            has_started: false,
+        }
+    }
+
+    fn start_body(&mut self) -> io::Result<()> {
+        write!(self.output, "<table>")?;
+        self.record_end.write(&mut self.output)?;
+        write!(self.output, "  <tbody>")?;
+        self.record_end.write(&mut self.output)?;
+        self.has_started = true;
+        Ok(())
+    }
+
+    fn write_row(&mut self, items: &[&str], tag: &str) -> io::Result<()> {
+        let cells: String = items
+            .iter()
+            .map(|item| format!("<{}>{}</{}>", tag, to_html_text(item), tag))
+            .collect();
+        let row = format!("    <tr>{}</tr>", cells);
+        self.record_end.write_content(&mut self.output, &row)?;
+        self.record_end.write(&mut self.output)
+    }
+}
+
+impl<W: Write> OutputWriter for HtmlWriter<W> {
+    fn write_record(&mut self, items: &[&str]) -> io::Result<()> {
+        if !self.has_started {
+            self.start_body()?;
+        }
+        self.write_row(items, "td")
+    }
+
+    fn write_header(&mut self, items: &[&str]) -> io::Result<()> {
+        write!(self.output, "<table>")?;
+        self.record_end.write(&mut self.output)?;
+        write!(self.output, "  <thead>")?;
+        self.record_end.write(&mut self.output)?;
+        self.write_row(items, "th")?;
+        write!(self.output, "  </thead>")?;
+        self.record_end.write(&mut self.output)?;
+        write!(self.output, "  <tbody>")?;
+        self.record_end.write(&mut self.output)?;
+        self.has_started = true;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if !self.has_started {
+            self.start_body()?;
+        }
+        write!(self.output, "  </tbody>")?;
+        self.record_end.write(&mut self.output)?;
+        write!(self.output, "</table>")?;
+        self.record_end.write(&mut self.output)?;
+        self.output.flush()
+    }
+
+    fn record_end(&mut self) -> &mut RecordEnd {
+        &mut self.record_end
+    }
+}
+
+fn to_html_text(value: &str) -> String {
+    //! Escape the characters with a special meaning in HTML
+    let mut text = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            '&' => text.push_str("&amp;"),
+            '<' => text.push_str("&lt;"),
+            '>' => text.push_str("&gt;"),
+            '"' => text.push_str("&quot;"),
+            '\'' => text.push_str("&#39;"),
+            _ => text.push(character),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod unit_tests_delimited_writer {
+    use super::{DelimitedWriter, OutputWriter, RecordFlags};
+
+    #[test]
+    fn test_01_join_with_delimiter() {
+        let mut output = vec![];
+        let mut writer = DelimitedWriter::new(&mut output, ",", "\n");
+
+        writer.write_header(&["a", "b"]).unwrap();
+        writer.write_record(&["1", "2"]).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!("a,b\n1,2\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_02_record_separator() {
+        let mut output = vec![];
+        let mut writer = DelimitedWriter::new(&mut output, ",", "\0");
+
+        writer.write_record(&["a", "b"]).unwrap();
+        writer.write_record(&["x"]).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!("a,b\0x\0", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_03_carriage_return_on_some_records() {
+        let mut output = vec![];
+        let mut writer = DelimitedWriter::new(&mut output, ",", "\n");
+
+        writer.set_record_flags(RecordFlags {
+            has_carriage_return: true,
+            has_raw_bytes: false,
+        });
+        writer.write_record(&["a", "b"]).unwrap();
+        writer.set_record_flags(RecordFlags::default());
+        writer.write_record(&["x"]).unwrap();
+
+        assert_eq!("a,b\r\nx\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_04_raw_bytes_only_on_flagged_records() {
+        let mut output = vec![];
+        let mut writer = DelimitedWriter::new(&mut output, ",", "\n");
+        writer.set_record_flags(RecordFlags {
+            has_carriage_return: false,
+            has_raw_bytes: true,
+        });
+        writer.write_record(&["caf\u{10ffe9}", "b"]).unwrap();
+        writer.set_record_flags(RecordFlags::default());
+        writer.write_record(&["\u{10ffe9}"]).unwrap();
+
+        let expected = [b"caf\xe9,b\n".as_slice(), "\u{10ffe9}\n".as_bytes()].concat();
+        assert_eq!(expected, output);
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_json_lines_writer {
+    use super::{JsonLinesWriter, OutputWriter};
+
+    #[test]
+    fn test_01_array() {
+        let mut output = vec![];
+        let mut writer = JsonLinesWriter::new(&mut output);
+
+        writer.write_record(&["a", "1"]).unwrap();
+
+        assert_eq!("[\"a\",\"1\"]\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_02_escaping() {
+        let mut output = vec![];
+        let mut writer = JsonLinesWriter::new(&mut output);
+
+        writer.write_record(&["say \"hi\"\t\\", "\u{1}"]).unwrap();
+
+        assert_eq!(
+            "[\"say \\\"hi\\\"\\t\\\\\",\"\\u0001\"]\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_03_object_with_header() {
+        let mut output = vec![];
+        let mut writer = JsonLinesWriter::new(&mut output);
+
+        writer.write_header(&["Name", "Age"]).unwrap();
+        writer.write_record(&["Bob", "45", "x"]).unwrap();
+
+        assert_eq!(
+            "{\"Name\":\"Bob\",\"Age\":\"45\",\"2\":\"x\"}\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_04_empty_record() {
+        let mut output = vec![];
+        let mut writer = JsonLinesWriter::new(&mut output);
+
+        writer.write_record(&[]).unwrap();
+
+        assert_eq!("[]\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_05_repeated_keys() {
+        let mut output = vec![];
+        let mut writer = JsonLinesWriter::new(&mut output);
+
+        writer.write_header(&["Name", "Name", "2"]).unwrap();
+        writer.write_record(&["Bob", "Bob", "45", "x"]).unwrap();
+
+        assert_eq!(
+            "{\"Name\":\"Bob\",\"Name_2\":\"Bob\",\"2\":\"45\",\"3\":\"x\"}\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_csv_writer {
+    use super::{CsvWriter, OutputWriter, RecordFlags};
+
+    #[test]
+    fn test_01_plain_fields() {
+        base_test(&["a", "b c", "1"], "a,b c,1\n");
+    }
+
+    #[test]
+    fn test_02_field_with_delimiter() {
+        base_test(&["Doe, John", "1"], "\"Doe, John\",1\n");
+    }
+
+    #[test]
+    fn test_03_field_with_quotes() {
+        base_test(&["say \"hi\"", "1"], "\"say \"\"hi\"\"\",1\n");
+    }
+
+    #[test]
+    fn test_04_field_with_newline() {
+        base_test(&["two\nlines", "1"], "\"two\nlines\",1\n");
+    }
+
+    #[test]
+    fn test_05_carriage_return() {
+        let mut output = vec![];
+        let mut writer = CsvWriter::new(&mut output);
+
+        writer.set_record_flags(RecordFlags {
+            has_carriage_return: true,
+            has_raw_bytes: false,
+        });
+        writer.write_record(&["a", "b"]).unwrap();
+
+        assert_eq!("a,b\r\n", String::from_utf8(output).unwrap());
+    }
+
+    fn base_test(items: &[&str], expected: &str) {
+        let mut output = vec![];
+        let mut writer = CsvWriter::new(&mut output);
+
+        writer.write_record(items).unwrap();
+
+        assert_eq!(expected, String::from_utf8(output).unwrap());
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_tsv_writer {
+    use super::{OutputWriter, TsvWriter};
+
+    #[test]
+    fn test_01_plain_fields() {
+        base_test(&["a", "b,c", "\"1\""], "a\tb,c\t\"1\"\n");
+    }
+
+    #[test]
+    fn test_02_escapes() {
+        base_test(&["a\tb", "c\\d", "e\r\nf"], "a\\tb\tc\\\\d\te\\r\\nf\n");
+    }
+
+    fn base_test(items: &[&str], expected: &str) {
+        let mut output = vec![];
+        let mut writer = TsvWriter::new(&mut output);
+
+        writer.write_record(items).unwrap();
+
+        assert_eq!(expected, String::from_utf8(output).unwrap());
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_table_writer {
+    use super::{OutputWriter, RecordFlags, TableWriter};
+
+    #[test]
+    fn test_01_aligned_columns() {
+        let expected = "Name      Age\nJohn Doe  28\nJane      4\n";
+
+        base_test(None, false, false, expected);
+    }
+
+    #[test]
+    fn test_02_header_separator() {
+        let expected = "Name      Age\n--------  ---\nJohn Doe  28\nJane      4\n";
+
+        base_test(None, false, true, expected);
+    }
+
+    #[test]
+    fn test_03_border() {
+        let expected = "┌──────────┬─────┐
+│ Name     │ Age │
+├──────────┼─────┤
+│ John Doe │ 28  │
+│ Jane     │ 4   │
+└──────────┴─────┘
+";
+
+        base_test(None, true, true, expected);
+    }
+
+    #[test]
+    fn test_04_sample() {
+        // The widths are taken from the first line only
+        let expected = "Name  Age\nJohn Doe  28\nJane  4\n";
+
+        base_test(Some(1), false, false, expected);
+    }
+
+    #[test]
+    fn test_05_multibyte_characters() {
+        let mut output = vec![];
+        let mut writer = TableWriter::new(&mut output, None, false);
+
+        writer.write_record(&["日本", "x"]).unwrap();
+        writer.write_record(&["abcde", "y"]).unwrap();
+        writer.write_record(&["ñ", "z"]).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(
+            "日本   x\nabcde  y\nñ      z\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_06_ragged_rows() {
+        let mut output = vec![];
+        let mut writer = TableWriter::new(&mut output, None, false);
+
+        writer.write_record(&["a"]).unwrap();
+        writer.write_record(&["bb", "c"]).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!("a   \nbb  c\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_07_buffered_rows_keep_their_carriage_return() {
+        let mut output = vec![];
+        let mut writer = TableWriter::new(&mut output, None, true);
+
+        writer.set_record_flags(RecordFlags {
+            has_carriage_return: true,
+            has_raw_bytes: false,
+        });
+        writer.write_record(&["a"]).unwrap();
+        writer.set_record_flags(RecordFlags::default());
+        writer.write_record(&["bb"]).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(
+            "┌────┐\r\n│ a  │\r\n│ bb │\n└────┘\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_08_buffered_rows_keep_their_raw_bytes() {
+        let mut output = vec![];
+        let mut writer = TableWriter::new(&mut output, None, false);
+
+        writer.set_record_flags(RecordFlags {
+            has_carriage_return: false,
+            has_raw_bytes: true,
+        });
+        writer.write_record(&["\u{10ffe9}", "a"]).unwrap();
+        writer.set_record_flags(RecordFlags::default());
+        writer.write_record(&["\u{10ffe9}", "b"]).unwrap();
+        writer.finish().unwrap();
+
+        let expected = [b"\xe9  a\n".as_slice(), "\u{10ffe9}  b\n".as_bytes()].concat();
+        assert_eq!(expected, output);
+    }
+
+    fn base_test(sample_size: Option<usize>, has_border: bool, has_header: bool, expected: &str) {
+        let mut output = vec![];
+        let mut writer = TableWriter::new(&mut output, sample_size, has_border);
+
+        let first_row = ["Name", "Age"];
+        if has_header {
+            writer.write_header(&first_row).unwrap();
+        } else {
+            writer.write_record(&first_row).unwrap();
+        }
+        writer.write_record(&["John Doe", "28"]).unwrap();
+        writer.write_record(&["Jane", "4"]).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(expected, String::from_utf8(output).unwrap());
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_markdown_writer {
+    use super::{MarkdownWriter, OutputWriter};
+
+    #[test]
+    fn test_01_with_header() {
+        let mut output = vec![];
+        let mut writer = MarkdownWriter::new(&mut output);
+
+        writer.write_header(&["Name", "Age"]).unwrap();
+        writer.write_record(&["John Doe", "28"]).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(
+            "| Name | Age |\n| --- | --- |\n| John Doe | 28 |\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_02_without_header() {
+        let mut output = vec![];
+        let mut writer = MarkdownWriter::new(&mut output);
+
+        writer.write_record(&["a", "b"]).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(
+            "|  |  |\n| --- | --- |\n| a | b |\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_03_escaping() {
+        let mut output = vec![];
+        let mut writer = MarkdownWriter::new(&mut output);
+
+        writer.write_header(&["a|b", "c\\d\ne"]).unwrap();
+
+        assert_eq!(
+            "| a\\|b | c\\\\d<br>e |\n| --- | --- |\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_html_writer {
+    use super::{HtmlWriter, OutputWriter};
+
+    #[test]
+    fn test_01_with_header() {
+        let mut output = vec![];
+        let mut writer = HtmlWriter::new(&mut output);
+
+        writer.write_header(&["Name", "Age"]).unwrap();
+        writer.write_record(&["John Doe", "28"]).unwrap();
+        writer.finish().unwrap();
+
+        let expected = "<table>
+  <thead>
+    <tr><th>Name</th><th>Age</th></tr>
+  </thead>
+  <tbody>
+    <tr><td>John Doe</td><td>28</td></tr>
+  </tbody>
+</table>
+";
+        assert_eq!(expected, String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_02_without_header() {
+        let mut output = vec![];
+        let mut writer = HtmlWriter::new(&mut output);
+
+        writer.write_record(&["a"]).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(
+            "<table>\n  <tbody>\n    <tr><td>a</td></tr>\n  </tbody>\n</table>\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_03_escaping() {
+        let mut output = vec![];
+        let mut writer = HtmlWriter::new(&mut output);
+
+        writer.write_record(&["<b>\"Tom\" & 'Jerry'</b>"]).unwrap();
+
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .contains("<td>&lt;b&gt;&quot;Tom&quot; &amp; &#39;Jerry&#39;&lt;/b&gt;</td>"));
+    }
+}
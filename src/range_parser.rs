@@ -8,11 +8,25 @@
 //!
 //! The range `1:8:2` would result in indexes `[1, 3, 5, 7]`
 //! ```rust
-//! parse_range("1:8:2", 10) => Range(1, 8, 2)
+//! parse_range_list("1:8:2").resolve(10, ..) => [Range(1, 8, 2)]
 //! ```
 //! `Range` is a struct that represents the range, with a start, end and a setp
 //!
 //! Multiple ranges are supported, if they are delimited with `,`
+//!
+//! A range can also be written with an inclusive end as `N..=M:S`, so `2..=5` is the same as `2:6`.
+//!
+//! Besides integers, `N` and `M` can be symbolic bounds, optionally followed by an offset like `+1` or `-2`:
+//!     - `end` - the number of items in the line, so `end-2` is the same as `-2`.
+//!       As a single item or an inclusive end, `end` is the last item, like `-1`, so `..=end` is the same as `:`
+//!     - `mid` - half of the number of items in the line
+//!     - `/regex/` - the index of the first item matching the regex, or `end` if none does
+//!
+//! ```rust
+//! parse_range_list("/ERROR/+1:end-1").resolve(10, ..) => [Range(index of first match + 1, 9, 1)]
+//! ```
+use regex::Regex;
+use std::fmt;
 
 /// Sturct that represents a range
 /// Each range has a start, end and a step
@@ -35,100 +49,351 @@ impl Range {
     }
 }
 
-pub fn parse_range(input: &str, n: usize) -> Result<Vec<Range>, String> {
-    //! Convert a string into a collection of `Range` objects
-    //! If the `input` string is not a valid range, an `Err` is returned
-    //!
-    //! Split the string on `,` and parse each range separetly.
-    //! If one of the ranges is not parsable, the whole input is deemed unparsable.
-    //!
-    //! ```rust
-    //! assert_eq!(parse_range("1:8:2,10,-4:-2", 20),Ok(vec![Range::new(1, 8, 2), Range::new(10,11,1), Range(16, 19, 1)]))
-    //! ```
-    let ranges = input.split(',');
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.start, self.end, self.step)
+    }
+}
 
-    let mut result = vec![];
+/// A bound of a range, as written in the LIST
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Bound {
+    Index(i32),
+    // A symbolic bound, resolved against each line, followed by its offset
+    Symbolic(Symbol, i32),
+}
+
+#[derive(Debug, Clone)]
+enum Symbol {
+    End,
+    Mid,
+    Pattern(Regex),
+}
+
+/// How the end of a range is written
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RangeEnd {
+    // `N:M`, or `N:` when there is no bound
+    Exclusive(Option<Bound>),
+    // `N..=M`, or `N..=` when there is no bound
+    Inclusive(Option<Bound>),
+    // `N`, a single item
+    Single,
+}
+
+/// A single range of a LIST, before it is resolved against a line
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RangeSpec {
+    start: Option<Bound>,
+    end: RangeEnd,
+    step: i32,
+}
+
+/// Struct that represents a parsed LIST.
+/// The regexes of its `/regex/` bounds are compiled once, and the ranges are resolved against each line with `resolve`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeList {
+    ranges: Vec<RangeSpec>,
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Symbol::Pattern(pattern), Symbol::Pattern(other_pattern)) => {
+                pattern.as_str() == other_pattern.as_str()
+            }
+            (Symbol::End, Symbol::End) | (Symbol::Mid, Symbol::Mid) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Bound {
+    fn resolve(&self, n: usize, find_match: &dyn Fn(&Regex) -> Option<usize>) -> i32 {
+        //! Resolve the bound against a line with `n` items.
+        //! A `/regex/` that matches no item is the same as `end`.
+        let (base, offset) = match self {
+            Bound::Index(index) => return *index,
+            Bound::Symbolic(Symbol::End, offset) => (n, offset),
+            Bound::Symbolic(Symbol::Mid, offset) => (n / 2, offset),
+            Bound::Symbolic(Symbol::Pattern(pattern), offset) => {
+                (find_match(pattern).unwrap_or(n), offset)
+            }
+        };
+
+        (base as i32).saturating_add(*offset)
+    }
+
+    fn is_fixed(&self) -> bool {
+        //! Tell whether the bound is the same for all lines, which is only the case for a non-negative index
+        matches!(self, Bound::Index(index) if *index >= 0)
+    }
+
+    fn resolve_inclusive(&self, n: usize, find_match: &dyn Fn(&Regex) -> Option<usize>) -> i32 {
+        //! Resolve the bound as a single item or an inclusive end, where `end` is the last item, like `-1`
+        match self {
+            Bound::Symbolic(Symbol::End, 0) => -1,
+            bound => bound.resolve(n, find_match),
+        }
+    }
+}
+
+impl RangeSpec {
+    fn resolve(&self, n: usize, find_match: &dyn Fn(&Regex) -> Option<usize>) -> Range {
+        //! Resolve the range against a line with `n` items.
+        //!
+        //! A single item `N` is the range `N:N+1`, and `N..=M` is `N:M+1`.
+        //! An inclusive end of `-1` is converted to `n`, since `N:0` would select nothing.
+        let start = match (&self.start, &self.end) {
+            (Some(bound), RangeEnd::Single) => bound.resolve_inclusive(n, find_match),
+            (Some(bound), _) => bound.resolve(n, find_match),
+            (None, _) => 0,
+        };
 
-    for range in ranges {
-        let parse_result = parse_single_range(range, n);
+        let end = match &self.end {
+            RangeEnd::Exclusive(end) => end
+                .as_ref()
+                .map_or(n as i32, |bound| bound.resolve(n, find_match)),
+            RangeEnd::Inclusive(end) => {
+                match end
+                    .as_ref()
+                    .map(|bound| bound.resolve_inclusive(n, find_match))
+                {
+                    None | Some(-1) => n as i32,
+                    Some(end) => end.saturating_add(1),
+                }
+            }
+            RangeEnd::Single if start == -1 => n as i32,
+            RangeEnd::Single => start.saturating_add(1),
+        };
 
-        match parse_result {
-            Ok(range) => result.push(range),
-            Err(error) => return Err(error),
+        Range::new(start, end, self.step)
+    }
+
+    fn is_fixed(&self) -> bool {
+        //! Tell whether the range is resolved the same way for all lines.
+        //! An open end is the number of items, and so are `..=-1` and `..=end`.
+        let is_start_fixed = self.start.as_ref().is_none_or(Bound::is_fixed);
+        let is_end_fixed = match &self.end {
+            RangeEnd::Exclusive(end) | RangeEnd::Inclusive(end) => {
+                end.as_ref().is_some_and(Bound::is_fixed)
+            }
+            RangeEnd::Single => true,
         };
+
+        is_start_fixed && is_end_fixed
     }
+}
 
-    Ok(result)
+impl RangeList {
+    pub fn resolve(&self, n: usize, find_match: &dyn Fn(&Regex) -> Option<usize>) -> Vec<Range> {
+        //! Resolve the ranges against a line with `n` items.
+        //! `find_match` returns the index of the first item in the line matching a `/regex/` bound.
+        self.ranges
+            .iter()
+            .map(|range| range.resolve(n, find_match))
+            .collect()
+    }
 }
 
-fn parse_single_range(field: &str, n: usize) -> Result<Range, String> {
-    //! Parses a string containing a single range into a `Range` object.
+pub fn parse_range_list(input: &str) -> Result<RangeList, String> {
+    //! Convert a LIST into a `RangeList`, to be resolved against each line.
+    //! If the `input` string is not a valid LIST, an `Err` is returned
+    //!
+    //! Split the string on `,` and parse each range separetly.
+    //! If one of the ranges is not parsable, the whole input is deemed unparsable.
+    let ranges = split_outside_patterns(input, ",")
+        .into_iter()
+        .map(parse_range_spec)
+        .collect::<Result<Vec<RangeSpec>, String>>()?;
+
+    Ok(RangeList { ranges })
+}
+
+pub fn parse_fixed_range(input: &str) -> Option<Vec<Range>> {
+    //! Parse the ranges once for all lines, if they don't depend on the line.
+    //! That is the case when they have no symbolic bounds, no open end and no negative indexes.
+    //!
+    //! Return `None` if the ranges have to be resolved for each line with `RangeList::resolve`.
+    let list = parse_range_list(input).ok()?;
+    let is_fixed = list.ranges.iter().all(RangeSpec::is_fixed);
+
+    is_fixed.then(|| list.resolve(0, &|_| None))
+}
+
+fn parse_range_spec(field: &str) -> Result<RangeSpec, String> {
+    //! Parses a string containing a single range into a `RangeSpec`.
     //!
     //! If the `field` is empty, the function returns an error.
     //!
     //! Parsing is done by splitting the input on `:`.
-    //!     - If the start is not given, set it to 0
-    //!     - If the end is not given, set it to n
-    //!     - If the step is not given ,set it to 1
+    //!     - If the start is not given, it is 0
+    //!     - If the end is not given, it is n
+    //!     - If the step is not given, it is 1
     //!
     //! ```rust
-    //! assert_eq!(parse_single_range("1:8:2"), Ok(Range::new(1, 8, 2)))
+    //! assert_eq!(parse_range_spec("1:8:2").map(|range| range.resolve(10, &|_| None)), Ok(Range::new(1, 8, 2)))
     //! ```
     let error_message = String::from("Invalid range");
     if field.is_empty() {
         return Err(error_message);
     }
 
-    let groups: Vec<&str> = field.split(':').collect();
+    let groups: Vec<&str> = split_outside_patterns(field, ":");
+
+    let inclusive_bounds = split_outside_patterns(groups[0], "..=");
+    if inclusive_bounds.len() == 2 {
+        return parse_inclusive_range(&inclusive_bounds, &groups[1..]);
+    }
 
-    let colon_count = field.match_indices(':').count();
+    let colon_count = groups.len() - 1;
+    if colon_count >= 3 {
+        return Err(error_message);
+    }
 
-    if colon_count < 3 {
-        let parsed_start = get_parsed_item(groups[0], 0);
+    let start = get_parsed_item(groups[0])?;
+    let end = match colon_count {
+        0 => RangeEnd::Single,
+        _ => RangeEnd::Exclusive(get_parsed_item(groups[1])?),
+    };
+    let step = match colon_count {
+        2 => get_parsed_step(groups[2])?,
+        _ => 1,
+    };
+
+    Ok(RangeSpec { start, end, step })
+}
 
-        let parsed_end = if colon_count != 0 {
-            get_parsed_item(groups[1], n as i32)
-        } else {
-            match parsed_start.clone() {
-                Ok(start) => {
-                    if start == -1 {
-                        Ok(n as i32)
-                    } else {
-                        Ok(start + 1)
-                    }
-                }
-                Err(parse_error) => Err(parse_error),
-            }
-        };
+fn parse_inclusive_range(bounds: &[&str], steps: &[&str]) -> Result<RangeSpec, String> {
+    //! Parses an inclusive `N..=M:S` range into a `RangeSpec`.
+    //!
+    //! The end is converted to an exclusive one when the range is resolved, so `N..=M` is the same as `N:M+1`,
+    //! except for `N..=-1` and `N..=end`, which are the same as `N:`.
+    //!
+    //! ```rust
+    //! assert_eq!(parse_inclusive_range(["2", "8"], ["2"]).resolve(10), Range::new(2, 9, 2))
+    //! ```
+    if steps.len() > 1 {
+        return Err(String::from("Invalid range"));
+    }
+
+    let start = get_parsed_item(bounds[0])?;
+    let end = RangeEnd::Inclusive(get_parsed_item(bounds[1])?);
+
+    let step = match steps.first() {
+        Some(raw_step) => get_parsed_step(raw_step)?,
+        None => 1,
+    };
+
+    Ok(RangeSpec { start, end, step })
+}
+
+fn get_parsed_item(raw_item: &str) -> Result<Option<Bound>, String> {
+    //! Extract the bound from a string.
+    //! If empty, return `None`, so the default value is used. Otherwise, return the result of the parse operation.
+    //!
+    //! Symbolic bounds (`end`, `mid`, `/regex/`) are parsed here, followed by their offset.
+    if raw_item.is_empty() {
+        return Ok(None);
+    }
 
-        let parsed_step = if colon_count == 2 {
-            get_parsed_item(groups[2], 1)
-        } else {
-            Ok(1)
+    if let Ok(value) = raw_item.parse::<i32>() {
+        return Ok(Some(Bound::Index(value)));
+    }
+
+    let error_message = String::from("Invalid range");
+
+    let (symbol, offset) = if let Some(rest) = raw_item.strip_prefix('/') {
+        let pattern_end = match find_pattern_end(rest) {
+            Some(index) => index,
+            None => return Err(error_message),
+        };
+        let pattern = match Regex::new(&rest[..pattern_end].replace("\\/", "/")) {
+            Ok(pattern) => pattern,
+            Err(_) => return Err(error_message),
         };
 
-        match (parsed_start, parsed_end, parsed_step) {
-            (Ok(start), Ok(end), Ok(step)) => Ok(Range::new(start, end, step)),
-            _ => Err(error_message),
+        (Symbol::Pattern(pattern), &rest[pattern_end + 1..])
+    } else if let Some(offset) = raw_item.strip_prefix("end") {
+        (Symbol::End, offset)
+    } else if let Some(offset) = raw_item.strip_prefix("mid") {
+        (Symbol::Mid, offset)
+    } else {
+        return Err(error_message);
+    };
+
+    if offset.is_empty() {
+        Ok(Some(Bound::Symbolic(symbol, 0)))
+    } else if offset.starts_with(['+', '-']) {
+        match offset.parse::<i32>() {
+            Ok(offset) => Ok(Some(Bound::Symbolic(symbol, offset))),
+            Err(_) => Err(error_message),
         }
     } else {
         Err(error_message)
     }
 }
 
-fn get_parsed_item(raw_item: &str, default: i32) -> Result<i32, std::num::ParseIntError> {
-    //! Extract the value from a string.
-    //! If empty, return default value. Otherwise, return the result of the parse operation.
+fn get_parsed_step(raw_item: &str) -> Result<i32, String> {
+    //! Extract the step from a string. Symbolic steps are not supported.
     if !raw_item.is_empty() {
-        raw_item.parse::<i32>()
+        raw_item
+            .parse::<i32>()
+            .map_err(|_| String::from("Invalid range"))
     } else {
-        Ok(default)
+        Ok(1)
+    }
+}
+
+fn find_pattern_end(pattern: &str) -> Option<usize> {
+    //! Find the index of the `/` closing a regex bound. `\/` does not close it.
+    let mut is_escaped = false;
+    for (index, character) in pattern.char_indices() {
+        match character {
+            '\\' => is_escaped = !is_escaped,
+            '/' if !is_escaped => return Some(index),
+            _ => is_escaped = false,
+        }
     }
+    None
+}
+
+fn split_outside_patterns<'a>(input: &'a str, separator: &str) -> Vec<&'a str> {
+    //! Split the input on `separator`, ignoring separators inside `/regex/` bounds.
+    let mut result = vec![];
+    let mut start = 0;
+    let mut is_in_pattern = false;
+    let mut is_escaped = false;
+
+    for (index, character) in input.char_indices() {
+        if index < start {
+            // Still inside the previous separator
+            continue;
+        }
+        match character {
+            '\\' if is_in_pattern => {
+                is_escaped = !is_escaped;
+                continue;
+            }
+            '/' if !is_escaped => is_in_pattern = !is_in_pattern,
+            _ if !is_in_pattern && input[index..].starts_with(separator) => {
+                result.push(&input[start..index]);
+                start = index + separator.len();
+            }
+            _ => {}
+        }
+        is_escaped = false;
+    }
+    result.push(&input[start..]);
+
+    result
 }
 
 #[cfg(test)]
 mod unit_tests_parse_single_range {
-    use super::{parse_single_range, Range};
+    use super::{parse_range_list, Range};
     /*
     N:M:S
 
@@ -367,10 +632,11 @@ mod unit_tests_parse_single_range {
         base_test(&fields, expected_range);
     }
 
-    fn base_test(fields: &String, expected_range: Result<Range, String>) {
-        let actual_range = parse_single_range(fields, SAMPLE_LENGTH);
+    fn base_test(fields: &str, expected_range: Result<Range, String>) {
+        let actual_range =
+            parse_range_list(fields).map(|ranges| ranges.resolve(SAMPLE_LENGTH, &|_| None));
 
-        assert_eq!(actual_range, expected_range)
+        assert_eq!(actual_range, expected_range.map(|range| vec![range]))
     }
 }
 
@@ -381,7 +647,7 @@ mod unit_tests_parse_range {
     // multiple ranges
     // multiple ranges with 1 invalid
 
-    use super::{parse_range, parse_single_range, Range};
+    use super::{parse_range_list, Range};
 
     static SAMPLE_LENGTH: usize = 10;
     static SEPARATOR: &str = ",";
@@ -394,8 +660,7 @@ mod unit_tests_parse_range {
     #[test]
     fn test_01_1_range() {
         let fields = String::from("") + SAMPLE_RANGE_1;
-        let expected_range: Result<Vec<Range>, String> =
-            Ok(vec![parse_single_range(SAMPLE_RANGE_1, 3).unwrap()]);
+        let expected_range: Result<Vec<Range>, String> = Ok(vec![Range::new(0, 2, 1)]);
 
         base_test(&fields, expected_range);
     }
@@ -417,9 +682,9 @@ mod unit_tests_parse_range {
             + SEPARATOR
             + SAMPLE_RANGE_1;
         let expected_range: Result<Vec<Range>, String> = Ok(vec![
-            parse_single_range(SAMPLE_RANGE_1, 3).unwrap(),
-            parse_single_range(SAMPLE_RANGE_2, 13).unwrap(),
-            parse_single_range(SAMPLE_RANGE_1, 3).unwrap(),
+            Range::new(0, 2, 1),
+            Range::new(3, 7, 1),
+            Range::new(0, 2, 1),
         ]);
 
         base_test(&fields, expected_range);
@@ -440,9 +705,241 @@ mod unit_tests_parse_range {
         base_test(&fields, expected_range);
     }
 
-    fn base_test(fields: &String, expected_range: Result<Vec<Range>, String>) {
-        let actual_range = parse_range(fields, SAMPLE_LENGTH);
+    fn base_test(fields: &str, expected_range: Result<Vec<Range>, String>) {
+        let actual_range =
+            parse_range_list(fields).map(|ranges| ranges.resolve(SAMPLE_LENGTH, &|_| None));
 
         assert_eq!(actual_range, expected_range)
     }
 }
+
+#[cfg(test)]
+mod unit_tests_parse_symbolic_range {
+    use super::{parse_range_list, Range};
+    use regex::Regex;
+
+    static SAMPLE_LENGTH: usize = 10;
+    static SAMPLE_ITEMS: [&str; 4] = ["INFO", "user=1", "ERROR", "path=/var/log"];
+
+    static EXPECTED_ERROR: &str = "Invalid range";
+
+    #[test]
+    fn test_01_end_is_the_last_item() {
+        base_test("end", Ok(Range::new(-1, 10, 1)));
+    }
+
+    #[test]
+    fn test_02_end_with_offset() {
+        base_test("end-2:", Ok(Range::new(8, 10, 1)));
+    }
+
+    #[test]
+    fn test_03_mid() {
+        base_test("mid:end", Ok(Range::new(5, 10, 1)));
+    }
+
+    #[test]
+    fn test_04_mid_with_positive_offset() {
+        base_test(":mid+1:2", Ok(Range::new(0, 6, 2)));
+    }
+
+    #[test]
+    fn test_05_unknown_symbol() {
+        base_test("start:5", Err(String::from(EXPECTED_ERROR)));
+    }
+
+    #[test]
+    fn test_06_invalid_offset() {
+        base_test("end*2", Err(String::from(EXPECTED_ERROR)));
+    }
+
+    #[test]
+    fn test_07_unmatched_regex_is_end() {
+        base_test("/ERROR/:", Ok(Range::new(10, 10, 1)));
+    }
+
+    #[test]
+    fn test_08_unterminated_regex() {
+        base_test("/ERROR:", Err(String::from(EXPECTED_ERROR)));
+    }
+
+    #[test]
+    fn test_09_regex_with_items() {
+        let ranges = parse_with_items("/ERROR/:");
+
+        assert_eq!(Ok(vec![Range::new(2, 4, 1)]), ranges);
+    }
+
+    #[test]
+    fn test_10_single_regex_with_offset() {
+        let ranges = parse_with_items("/user/+1");
+
+        assert_eq!(Ok(vec![Range::new(2, 3, 1)]), ranges);
+    }
+
+    #[test]
+    fn test_11_regex_containing_separators() {
+        let ranges = parse_with_items("/^[A-Z]{1,5}$/:/=\\//,0");
+
+        assert_eq!(Ok(vec![Range::new(0, 3, 1), Range::new(0, 1, 1)]), ranges);
+    }
+
+    #[test]
+    fn test_12_end_after_offset_is_out_of_bounds() {
+        base_test("end+1", Ok(Range::new(11, 12, 1)));
+    }
+
+    #[test]
+    fn test_13_offset_past_the_largest_index() {
+        base_test("end+2147483647", Ok(Range::new(i32::MAX, i32::MAX, 1)));
+        base_test("mid-2147483647:", Ok(Range::new(-2147483642, 10, 1)));
+    }
+
+    #[test]
+    fn test_14_resolved_against_each_line() {
+        let ranges = parse_range_list("/ERROR/:").unwrap();
+        let other_items = ["ERROR", "INFO"];
+        let find_match =
+            |pattern: &Regex| other_items.iter().position(|item| pattern.is_match(item));
+
+        assert_eq!(vec![Range::new(0, 2, 1)], ranges.resolve(2, &find_match));
+        assert_eq!(Ok(vec![Range::new(2, 4, 1)]), parse_with_items("/ERROR/:"));
+    }
+
+    fn parse_with_items(fields: &str) -> Result<Vec<Range>, String> {
+        let find_match =
+            |pattern: &Regex| SAMPLE_ITEMS.iter().position(|item| pattern.is_match(item));
+
+        parse_range_list(fields).map(|ranges| ranges.resolve(SAMPLE_ITEMS.len(), &find_match))
+    }
+
+    fn base_test(fields: &str, expected_range: Result<Range, String>) {
+        let actual_range =
+            parse_range_list(fields).map(|ranges| ranges.resolve(SAMPLE_LENGTH, &|_| None));
+
+        assert_eq!(actual_range, expected_range.map(|range| vec![range]))
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_parse_inclusive_range {
+    use super::{parse_range_list, Range};
+
+    static SAMPLE_LENGTH: usize = 10;
+
+    static EXPECTED_ERROR: &str = "Invalid range";
+
+    #[test]
+    fn test_01_positive_positive() {
+        base_test("2..=5", Ok(Range::new(2, 6, 1)));
+    }
+
+    #[test]
+    fn test_02_positive_positive_step() {
+        base_test("2..=8:2", Ok(Range::new(2, 9, 2)));
+    }
+
+    #[test]
+    fn test_03_positive_negative_step() {
+        base_test("2..=5:-1", Ok(Range::new(2, 6, -1)));
+    }
+
+    #[test]
+    fn test_04_negative_negative() {
+        base_test("-4..=-2", Ok(Range::new(-4, -1, 1)));
+    }
+
+    #[test]
+    fn test_05_until_last() {
+        base_test("-3..=-1", Ok(Range::new(-3, SAMPLE_LENGTH as i32, 1)));
+    }
+
+    #[test]
+    fn test_06_empty_bounds() {
+        base_test("..=", Ok(Range::new(0, SAMPLE_LENGTH as i32, 1)));
+    }
+
+    #[test]
+    fn test_07_symbolic_end() {
+        base_test("mid..=end-1", Ok(Range::new(5, 10, 1)));
+    }
+
+    #[test]
+    fn test_08_two_steps() {
+        base_test("2..=8:2:1", Err(String::from(EXPECTED_ERROR)));
+    }
+
+    #[test]
+    fn test_09_mixed_with_exclusive() {
+        assert_eq!(
+            Ok(vec![Range::new(0, 1, 1), Range::new(2, 5, 1)]),
+            parse_range_list("0,2..=4").map(|ranges| ranges.resolve(SAMPLE_LENGTH, &|_| None))
+        );
+    }
+
+    #[test]
+    fn test_10_until_end() {
+        base_test("..=end", Ok(Range::new(0, SAMPLE_LENGTH as i32, 1)));
+        base_test("2..=end:2", Ok(Range::new(2, SAMPLE_LENGTH as i32, 2)));
+    }
+
+    #[test]
+    fn test_11_until_end_with_offset() {
+        base_test("..=end-2", Ok(Range::new(0, 9, 1)));
+    }
+
+    #[test]
+    fn test_12_largest_end() {
+        base_test("0..=2147483647", Ok(Range::new(0, i32::MAX, 1)));
+    }
+
+    fn base_test(fields: &str, expected_range: Result<Range, String>) {
+        let actual_range =
+            parse_range_list(fields).map(|ranges| ranges.resolve(SAMPLE_LENGTH, &|_| None));
+
+        assert_eq!(actual_range, expected_range.map(|range| vec![range]))
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_parse_fixed_range {
+    use super::{parse_fixed_range, Range};
+
+    #[test]
+    fn test_01_fixed_ranges() {
+        assert_eq!(
+            Some(vec![
+                Range::new(0, 1, 1),
+                Range::new(2, 5, 2),
+                Range::new(3, 7, 1)
+            ]),
+            parse_fixed_range("0,2:5:2,3..=6")
+        );
+    }
+
+    #[test]
+    fn test_02_ranges_depending_on_the_line() {
+        assert_eq!(None, parse_fixed_range("2:"));
+        assert_eq!(None, parse_fixed_range("-3:-1"));
+        assert_eq!(None, parse_fixed_range("0,end-2"));
+        assert_eq!(None, parse_fixed_range(":mid"));
+        assert_eq!(None, parse_fixed_range("/ERROR/:3"));
+        assert_eq!(None, parse_fixed_range("-1"));
+        assert_eq!(None, parse_fixed_range("2..=-1"));
+        assert_eq!(None, parse_fixed_range("0..=end"));
+        assert_eq!(None, parse_fixed_range("..="));
+    }
+
+    #[test]
+    fn test_03_invalid_range() {
+        assert_eq!(None, parse_fixed_range("a:b"));
+    }
+
+    #[test]
+    fn test_04_fixed_bounds_with_defaults() {
+        assert_eq!(
+            Some(vec![Range::new(0, 4, 1), Range::new(0, 3, 1)]),
+            parse_fixed_range(":4,..=2")
+        );
+    }
+}
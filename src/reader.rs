@@ -0,0 +1,929 @@
+//! Module, containing the splitting of the input into records
+//!
+//! By default each line is a record. With `--record-separator` any string can end a record,
+//! e.g. `\r\n`, `\x1e` or `---\n`. `-z` is the same as `--record-separator '\0'`.
+//!
+//! An empty separator enables paragraph mode, where the records are separated by one or more blank lines,
+//! and the line breaks around each record are removed.
+//!
+//! A carriage return at the end of a record (from CRLF line endings) is not part of the last field.
+//! What happens to it is decided by `--crlf`:
+//!     - `strip` - it is removed
+//!     - `keep` - it is removed, and added back at the end of each output record
+//!     - `auto` - like `keep` if the first record ends with a carriage return, otherwise it is left as it is.
+//!       It is decided once, so the records read later with `-F`, `-j` or `--line-buffered` are handled the same way
+//!
+//! Regular files are mapped into memory, and the records are borrowed from the map
+//! (pipes and stdin are read into a buffer). The separators are searched with `memchr`.
+//!
+//! Compressed inputs (gzip, zstd, bzip2 and xz) are detected by their magic bytes and decompressed
+//! while reading, unless `--decompress=none` is given.
+//!
+//! With `-F` (`--follow`), the file is read with a `FollowReader` instead, which keeps reading it as it grows,
+//! and reopens it after it is rotated, like `tail -F`. With `-j` and `--line-buffered`, the standard input is read
+//! with a `StreamReader`, in batches of records that are cut as they arrive.
+//!
+//! Invalid UTF-8 is handled by `--invalid-utf8`, for each record on its own (see the `encoding` module).
+//!
+//! ```rust
+//! split_records("a\nb\n\n\nc\n", &RecordSeparator::Paragraph) => ["a\nb", "c"]
+//! remove_carriage_returns(["a,b\r", "c,d"], CrlfPolicy::Auto) => (["a,b", "c,d"], [true, false])
+//! ```
+
+use std::borrow::Cow;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::ops::Deref;
+use std::os::unix::fs::MetadataExt;
+use std::thread;
+use std::time::Duration;
+
+use bzip2::read::MultiBzDecoder;
+use encoding_rs::Encoding;
+use flate2::read::MultiGzDecoder;
+use memchr::memmem;
+use memmap2::Mmap;
+use xz2::read::XzDecoder;
+
+use crate::encoding::{decode, decode_invalid_utf8, InvalidUtf8, StreamDecoder};
+
+// How often a followed file is checked for new records
+static FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(200);
+// How much of a streamed input is read at a time, at most
+static STREAM_READ_SIZE: usize = 64 * 1024;
+
+/// Enum that represents how the input is decompressed
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Decompression {
+    Auto,
+    None,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+    Xz,
+}
+
+// The magic bytes at the start of each compressed format
+static MAGIC_BYTES: [(&[u8], Compression); 4] = [
+    (&[0x1f, 0x8b], Compression::Gzip),
+    (&[0x28, 0xb5, 0x2f, 0xfd], Compression::Zstd),
+    (b"BZh", Compression::Bzip2),
+    (&[0xfd, b'7', b'z', b'X', b'Z', 0x00], Compression::Xz),
+];
+
+/// Enum that represents what ends a record in the input
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RecordSeparator {
+    Separator(String),
+    Paragraph,
+}
+
+impl RecordSeparator {
+    pub fn new(separator: &str) -> Self {
+        //! Create a record separator, where an empty separator means paragraph mode
+        if separator.is_empty() {
+            RecordSeparator::Paragraph
+        } else {
+            RecordSeparator::Separator(separator.to_owned())
+        }
+    }
+}
+
+/// Enum that represents what happens to the carriage returns at the end of the records
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CrlfPolicy {
+    Strip,
+    Keep,
+    Auto,
+    // What `auto` becomes when the first record has no carriage return: they are left as they are
+    Leave,
+}
+
+impl CrlfPolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        //! Get the policy from its `--crlf` name
+        match name {
+            "strip" => Some(CrlfPolicy::Strip),
+            "keep" => Some(CrlfPolicy::Keep),
+            "auto" => Some(CrlfPolicy::Auto),
+            _ => None,
+        }
+    }
+
+    pub fn resolve(self, records: &[Cow<str>]) -> Self {
+        //! Decide what `auto` means from the first record, so that the records read later are handled the same way.
+        //! Without any record, it's still undecided.
+        match (self, records.first()) {
+            (CrlfPolicy::Auto, Some(record)) if record.ends_with('\r') => CrlfPolicy::Keep,
+            (CrlfPolicy::Auto, Some(_)) => CrlfPolicy::Leave,
+            (policy, _) => policy,
+        }
+    }
+}
+
+/// The content of the input, either mapped into memory or read into a buffer
+pub enum Content {
+    Mapped(Mmap),
+    Buffered(Vec<u8>),
+}
+
+impl Deref for Content {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Content::Mapped(map) => map,
+            Content::Buffered(buffer) => buffer,
+        }
+    }
+}
+
+pub fn read_content(file_path: &str, decompression: Decompression) -> Result<Content, String> {
+    //! Read the whole input, from stdin if the path is `-`, decompressing it if needed.
+    //! Regular files are mapped into memory instead of being copied into a buffer.
+    //! The content is decoded into text by the `encoding` module.
+    let input: Box<dyn Read> = if file_path == "-" {
+        Box::new(io::stdin())
+    } else {
+        let file = match File::open(file_path) {
+            Ok(file) => file,
+            Err(error) => return Err(format!("Can't open {}: {}", file_path, error)),
+        };
+
+        if let Some(map) = map_file(&file) {
+            let compression = match decompression {
+                Decompression::Auto => detect_compression(&map),
+                Decompression::None => None,
+            };
+
+            return match compression {
+                Some(compression) => read_all(Box::new(&map[..]), Some(compression), file_path)
+                    .map(Content::Buffered),
+                None => Ok(Content::Mapped(map)),
+            };
+        }
+        Box::new(file)
+    };
+
+    let mut reader = BufReader::new(input);
+    let compression = detect_stream_compression(&mut reader, decompression, file_path)?;
+
+    read_all(Box::new(reader), compression, file_path).map(Content::Buffered)
+}
+
+fn detect_stream_compression(
+    reader: &mut impl BufRead,
+    decompression: Decompression,
+    file_path: &str,
+) -> Result<Option<Compression>, String> {
+    //! Detect the compression from the first bytes of the reader, without consuming them
+    match decompression {
+        Decompression::Auto => match reader.fill_buf() {
+            Ok(start) => Ok(detect_compression(start)),
+            Err(error) => Err(format!("Can't read {}: {}", file_path, error)),
+        },
+        Decompression::None => Ok(None),
+    }
+}
+
+fn map_file(file: &File) -> Option<Mmap> {
+    //! Map a non-empty regular file into memory. Anything else, such as a pipe, is read instead.
+    let metadata = file.metadata().ok()?;
+    if !metadata.is_file() || metadata.len() == 0 {
+        return None;
+    }
+
+    // SAFETY: the map is only read, and the file is not expected to be truncated while sbcut runs
+    unsafe { Mmap::map(file) }.ok()
+}
+
+fn read_all<'a>(
+    reader: Box<dyn BufRead + 'a>,
+    compression: Option<Compression>,
+    file_path: &str,
+) -> Result<Vec<u8>, String> {
+    //! Read everything from the reader, decompressing it if needed
+    let mut reader = open_decoder(reader, compression, file_path)?;
+
+    let mut buffer = vec![];
+    match reader.read_to_end(&mut buffer) {
+        Ok(_) => Ok(buffer),
+        Err(error) => Err(read_error(error, compression, file_path)),
+    }
+}
+
+fn open_decoder<'a>(
+    reader: Box<dyn BufRead + 'a>,
+    compression: Option<Compression>,
+    file_path: &str,
+) -> Result<Box<dyn Read + 'a>, String> {
+    //! Wrap the reader into the decoder of the compression, if there is one
+    match compression {
+        Some(Compression::Gzip) => Ok(Box::new(MultiGzDecoder::new(reader))),
+        Some(Compression::Zstd) => match zstd::Decoder::with_buffer(reader) {
+            Ok(decoder) => Ok(Box::new(decoder)),
+            Err(error) => Err(format!("Can't decompress {}: {}", file_path, error)),
+        },
+        Some(Compression::Bzip2) => Ok(Box::new(MultiBzDecoder::new(reader))),
+        Some(Compression::Xz) => Ok(Box::new(XzDecoder::new_multi_decoder(reader))),
+        None => Ok(Box::new(reader)),
+    }
+}
+
+fn read_error(error: io::Error, compression: Option<Compression>, file_path: &str) -> String {
+    //! Describe an error while reading the input, which comes from the decoder if it is compressed
+    match compression {
+        Some(_) => format!("Can't decompress {}: {}", file_path, error),
+        None => format!("Can't read {}: {}", file_path, error),
+    }
+}
+
+/// Reader that reads the standard input as it comes, in batches of complete records
+///
+/// Like with `FollowReader`, the bytes after the last separator wait for the rest of their record,
+/// until the input ends. The input is decoded into UTF-8 as it is read, so the records are returned as UTF-8.
+pub struct StreamReader {
+    input: Box<dyn Read>,
+    compression: Option<Compression>,
+    decoder: StreamDecoder,
+    separator: Vec<u8>,
+    buffer: Vec<u8>,
+    pending: Vec<u8>,
+    is_finished: bool,
+}
+
+impl StreamReader {
+    pub fn open_stdin(
+        separator: &RecordSeparator,
+        decompression: Decompression,
+        encoding: Option<&'static Encoding>,
+    ) -> Result<Self, String> {
+        let mut reader = BufReader::new(io::stdin());
+        let compression = detect_stream_compression(&mut reader, decompression, "-")?;
+        let input = open_decoder(Box::new(reader), compression, "-")?;
+
+        Ok(StreamReader::new(input, compression, encoding, separator))
+    }
+
+    fn new(
+        input: Box<dyn Read>,
+        compression: Option<Compression>,
+        encoding: Option<&'static Encoding>,
+        separator: &RecordSeparator,
+    ) -> Self {
+        StreamReader {
+            input,
+            compression,
+            decoder: StreamDecoder::new(encoding),
+            separator: separator_bytes(separator),
+            buffer: vec![0; STREAM_READ_SIZE],
+            pending: vec![],
+            is_finished: false,
+        }
+    }
+
+    pub fn read_records(&mut self, count: usize) -> Result<Option<Vec<u8>>, String> {
+        //! Read until at least `count` complete records are available or the input ends,
+        //! and return all the complete records read. Each read returns what the input has so far,
+        //! so records are returned as soon as they arrive.
+        //! At the end of the input, the rest is returned, with its last record without a separator.
+        //! Return `None` once everything is returned.
+        // The number of complete records, where the last of them ends, and how much of `pending` was searched
+        let mut found = 0;
+        let mut end = 0;
+        let mut searched = 0;
+
+        loop {
+            for position in memmem::find_iter(&self.pending[searched..], &self.separator) {
+                found += 1;
+                end = searched + position + self.separator.len();
+            }
+            if found >= count && found > 0 {
+                return Ok(Some(self.pending.drain(..end).collect()));
+            }
+            // A separator can be split between two reads
+            searched = end.max((self.pending.len() + 1).saturating_sub(self.separator.len()));
+
+            if self.is_finished {
+                return match self.pending.is_empty() {
+                    true => Ok(None),
+                    false => Ok(Some(std::mem::take(&mut self.pending))),
+                };
+            }
+
+            match self.input.read(&mut self.buffer) {
+                Ok(0) => {
+                    self.is_finished = true;
+                    self.decoder.decode(&[], true, &mut self.pending);
+                }
+                Ok(length) => self
+                    .decoder
+                    .decode(&self.buffer[..length], false, &mut self.pending),
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(read_error(error, self.compression, "-")),
+            }
+        }
+    }
+}
+
+/// Reader that keeps reading a file as it grows, like `tail -F`
+///
+/// Only complete records are returned, the bytes after the last separator wait for the rest of their record.
+/// If the file is replaced (e.g. by log rotation), the new file is read from its start,
+/// and if it is truncated, it is read again from its start.
+///
+/// The file is decoded into UTF-8 as it is read, like the streamed standard input, so the records are returned as UTF-8.
+/// A growing compressed file can't be decompressed, so it's an error unless `--decompress=none` is given.
+pub struct FollowReader {
+    path: String,
+    file: File,
+    // The device and inode of the followed file, which change when it is replaced
+    identity: (u64, u64),
+    position: u64,
+    decompression: Decompression,
+    encoding: Option<&'static Encoding>,
+    decoder: StreamDecoder,
+    separator: Vec<u8>,
+    pending: Vec<u8>,
+}
+
+impl FollowReader {
+    pub fn open(
+        path: &str,
+        separator: &RecordSeparator,
+        decompression: Decompression,
+        encoding: Option<&'static Encoding>,
+    ) -> Result<Self, String> {
+        let (file, identity) = open_followed(path)?;
+
+        Ok(FollowReader {
+            path: path.to_owned(),
+            file,
+            identity,
+            position: 0,
+            decompression,
+            encoding,
+            decoder: StreamDecoder::new(encoding),
+            separator: separator_bytes(separator),
+            pending: vec![],
+        })
+    }
+
+    pub fn read_available(&mut self) -> Result<Vec<u8>, String> {
+        //! Read what was written to the file since the last read, and return the complete records in it
+        let mut buffer = vec![];
+        match self.file.read_to_end(&mut buffer) {
+            Ok(length) => self.position += length as u64,
+            Err(error) => return Err(format!("Can't read {}: {}", self.path, error)),
+        }
+        if self.position == buffer.len() as u64
+            && self.decompression == Decompression::Auto
+            && detect_compression(&buffer).is_some()
+        {
+            return Err(format!(
+                "Can't follow the compressed file {}, use --decompress=none to read it as it is",
+                self.path
+            ));
+        }
+        self.decoder.decode(&buffer, false, &mut self.pending);
+
+        let end = match memmem::rfind(&self.pending, &self.separator) {
+            Some(index) => index + self.separator.len(),
+            None => return Ok(vec![]),
+        };
+        Ok(self.pending.drain(..end).collect())
+    }
+
+    pub fn wait_for_records(&mut self) -> Result<Vec<u8>, String> {
+        //! Wait until complete records are written to the file, and return them
+        loop {
+            let records = self.read_available()?;
+            if !records.is_empty() {
+                return Ok(records);
+            }
+
+            thread::sleep(FOLLOW_POLL_INTERVAL);
+            self.check_replaced()?;
+        }
+    }
+
+    fn check_replaced(&mut self) -> Result<(), String> {
+        //! Reopen the file if it was replaced, and read it again if it was truncated.
+        //! While the path doesn't exist, the old file is still followed.
+        let metadata = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(_) => return Ok(()),
+        };
+
+        if (metadata.dev(), metadata.ino()) != self.identity {
+            // The rest of the old file is read before switching to the new one, which is decoded on its own
+            let mut rest = self.read_available()?;
+            self.decoder.decode(&[], true, &mut self.pending);
+            self.decoder = StreamDecoder::new(self.encoding);
+            // A record left without its separator is ended, so it isn't joined with the new file
+            if !self.pending.is_empty() {
+                rest.append(&mut self.pending);
+                rest.extend_from_slice(&self.separator);
+            }
+            let (file, identity) = open_followed(&self.path)?;
+            eprintln!(
+                "sbcut: {} has been replaced, following the new file",
+                self.path
+            );
+
+            self.file = file;
+            self.identity = identity;
+            self.position = 0;
+            self.pending = rest;
+        } else if metadata.len() < self.position {
+            eprintln!("sbcut: {} has been truncated", self.path);
+            if let Err(error) = self.file.seek(SeekFrom::Start(0)) {
+                return Err(format!("Can't read {}: {}", self.path, error));
+            }
+            self.position = 0;
+            self.decoder = StreamDecoder::new(self.encoding);
+            self.pending.clear();
+        }
+
+        Ok(())
+    }
+}
+
+fn separator_bytes(separator: &RecordSeparator) -> Vec<u8> {
+    //! Get the bytes that end a record of a streamed input. In paragraph mode, that's a blank line.
+    match separator {
+        RecordSeparator::Separator(separator) => separator.as_bytes().to_vec(),
+        RecordSeparator::Paragraph => b"\n\n".to_vec(),
+    }
+}
+
+fn open_followed(path: &str) -> Result<(File, (u64, u64)), String> {
+    //! Open the followed file, and return it with its device and inode
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) => return Err(format!("Can't open {}: {}", path, error)),
+    };
+
+    match file.metadata() {
+        Ok(metadata) => Ok((file, (metadata.dev(), metadata.ino()))),
+        Err(error) => Err(format!("Can't open {}: {}", path, error)),
+    }
+}
+
+fn detect_compression(start: &[u8]) -> Option<Compression> {
+    //! Detect the compression from the first bytes of the input
+    MAGIC_BYTES
+        .iter()
+        .find(|(magic, _)| start.starts_with(magic))
+        .map(|(_, compression)| *compression)
+}
+
+pub fn split_records<'a>(content: &'a str, separator: &RecordSeparator) -> Vec<&'a str> {
+    //! Split the content into records, borrowed from the content.
+    //! If the content ends with the separator, the last record is empty.
+    //!
+    //! A separator found in valid UTF-8 always starts and ends on a character boundary,
+    //! so the byte positions from `split_positions` can be used to slice the text.
+    match separator {
+        RecordSeparator::Separator(separator) => {
+            split_positions(content.as_bytes(), separator.as_bytes())
+                .map(|(start, end)| &content[start..end])
+                .collect()
+        }
+        RecordSeparator::Paragraph => split_positions(content.as_bytes(), b"\n\n")
+            .map(|(start, end)| content[start..end].trim_matches('\n'))
+            .filter(|record| !record.is_empty())
+            .collect(),
+    }
+}
+
+pub fn decode_records<'a>(
+    content: &'a [u8],
+    encoding: Option<&'static Encoding>,
+    separator: &RecordSeparator,
+    invalid_utf8: InvalidUtf8,
+) -> Result<(Vec<Cow<'a, str>>, Vec<usize>), String> {
+    //! Decode the content into text and split it into records.
+    //! The records are borrowed from the content, unless it had to be transcoded.
+    //!
+    //! If the content is UTF-8 with invalid sequences, each record is decoded on its own,
+    //! according to `invalid_utf8`. Return the records and the numbers of the records with invalid UTF-8.
+    if let (None, None, Ok(text)) = (
+        encoding,
+        Encoding::for_bom(content),
+        std::str::from_utf8(content),
+    ) {
+        let records = split_records(text, separator);
+        return Ok((records.into_iter().map(Cow::Borrowed).collect(), vec![]));
+    }
+
+    let is_invalid_utf8 = encoding.is_none() && Encoding::for_bom(content).is_none();
+    if !is_invalid_utf8 || invalid_utf8 == InvalidUtf8::Error {
+        let text = decode(content, encoding)?;
+        let records = split_records(&text, separator)
+            .into_iter()
+            .map(|record| Cow::Owned(record.to_owned()))
+            .collect();
+        return Ok((records, vec![]));
+    }
+
+    let mut records = vec![];
+    let mut invalid_records = vec![];
+    for (index, record) in split_record_bytes(content, separator)
+        .into_iter()
+        .enumerate()
+    {
+        match std::str::from_utf8(record) {
+            Ok(text) => records.push(Cow::Borrowed(text)),
+            Err(_) => {
+                invalid_records.push(index + 1);
+                if let Some(text) = decode_invalid_utf8(record, invalid_utf8) {
+                    records.push(Cow::Owned(text));
+                }
+            }
+        }
+    }
+
+    Ok((records, invalid_records))
+}
+
+fn split_record_bytes<'a>(content: &'a [u8], separator: &RecordSeparator) -> Vec<&'a [u8]> {
+    //! Split the content into records, without decoding it
+    match separator {
+        RecordSeparator::Separator(separator) => split_positions(content, separator.as_bytes())
+            .map(|(start, end)| &content[start..end])
+            .collect(),
+        RecordSeparator::Paragraph => split_positions(content, b"\n\n")
+            .map(|(start, end)| trim_line_breaks(&content[start..end]))
+            .filter(|record| !record.is_empty())
+            .collect(),
+    }
+}
+
+fn split_positions<'a>(
+    content: &'a [u8],
+    separator: &'a [u8],
+) -> impl Iterator<Item = (usize, usize)> + 'a {
+    //! Return the start and the end of each part of the content between the separators
+    let ends = memmem::find_iter(content, separator).chain(std::iter::once(content.len()));
+    let mut start = 0;
+
+    ends.map(move |end| {
+        let part = (start, end);
+        start = (end + separator.len()).min(content.len());
+        part
+    })
+}
+
+fn trim_line_breaks(record: &[u8]) -> &[u8] {
+    let start = record.iter().position(|byte| *byte != b'\n');
+    let end = record.iter().rposition(|byte| *byte != b'\n');
+
+    match (start, end) {
+        (Some(start), Some(end)) => &record[start..=end],
+        _ => &[],
+    }
+}
+
+pub fn remove_carriage_returns(records: &mut [Cow<str>], policy: CrlfPolicy) -> Vec<bool> {
+    //! Remove the carriage return from the end of the records, according to the policy.
+    //! Return, for each record, whether its carriage return should be added back to its output record.
+    let is_restoring = match policy.resolve(records) {
+        CrlfPolicy::Strip => false,
+        CrlfPolicy::Keep => true,
+        CrlfPolicy::Auto | CrlfPolicy::Leave => return vec![false; records.len()],
+    };
+
+    records
+        .iter_mut()
+        .map(|record| {
+            let has_carriage_return = record.ends_with('\r');
+            match record {
+                Cow::Borrowed(text) => *text = text.strip_suffix('\r').unwrap_or(text),
+                Cow::Owned(text) => {
+                    if has_carriage_return {
+                        text.pop();
+                    }
+                }
+            }
+            is_restoring && has_carriage_return
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod unit_tests_stream_reader {
+    use super::{RecordSeparator, StreamReader};
+    use std::io::Read;
+
+    // Reader that returns its input a few bytes at a time, like a pipe
+    struct SlowReader {
+        input: &'static [u8],
+        read_size: usize,
+    }
+
+    impl Read for SlowReader {
+        fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+            let length = self.read_size.min(buffer.len()).min(self.input.len());
+            buffer[..length].copy_from_slice(&self.input[..length]);
+            self.input = &self.input[length..];
+            Ok(length)
+        }
+    }
+
+    #[test]
+    fn test_01_batches_of_records() {
+        base_test(b"a\nb\nc\nd", "\n", 2, vec!["a\nb\nc\n", "d"]);
+    }
+
+    #[test]
+    fn test_02_input_ending_with_separator() {
+        base_test(b"ab\ncd\nef\n", "\n", 2, vec!["ab\ncd\n", "ef\n"]);
+    }
+
+    #[test]
+    fn test_03_separator_split_between_reads() {
+        base_test(b"ab||cd||ef", "||", 1, vec!["ab||", "cd||", "ef"]);
+    }
+
+    #[test]
+    fn test_04_empty_input() {
+        base_test(b"", "\n", 1, vec![]);
+    }
+
+    #[test]
+    fn test_05_utf16_records() {
+        base_test(
+            b"\xff\xfea\x00\n\x00b\x00\n\x00c\x00",
+            "\n",
+            1,
+            vec!["a\n", "b\n", "c"],
+        );
+    }
+
+    fn base_test(input: &'static [u8], separator: &str, count: usize, expected: Vec<&str>) {
+        let input = Box::new(SlowReader {
+            input,
+            read_size: 3,
+        });
+        let mut reader = StreamReader::new(input, None, None, &RecordSeparator::new(separator));
+
+        let mut batches = vec![];
+        while let Some(batch) = reader.read_records(count).unwrap() {
+            batches.push(String::from_utf8(batch).unwrap());
+        }
+
+        assert_eq!(expected, batches);
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_detect_compression {
+    use super::{detect_compression, Compression};
+
+    #[test]
+    fn test_01_compressed_formats() {
+        assert_eq!(
+            Some(Compression::Gzip),
+            detect_compression(&[0x1f, 0x8b, 0x08, 0x00])
+        );
+        assert_eq!(
+            Some(Compression::Zstd),
+            detect_compression(&[0x28, 0xb5, 0x2f, 0xfd, 0x04])
+        );
+        assert_eq!(Some(Compression::Bzip2), detect_compression(b"BZh91AY&SY"));
+        assert_eq!(
+            Some(Compression::Xz),
+            detect_compression(b"\xfd7zXZ\x00\x00")
+        );
+    }
+
+    #[test]
+    fn test_02_plain_text() {
+        assert_eq!(None, detect_compression(b"Name,Age\n"));
+        assert_eq!(None, detect_compression(b""));
+        assert_eq!(None, detect_compression(&[0x1f]));
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_split_records {
+    use super::{split_records, RecordSeparator};
+
+    #[test]
+    fn test_01_lines() {
+        base_test("a,b\nc,d\n", "\n", vec!["a,b", "c,d", ""]);
+    }
+
+    #[test]
+    fn test_02_crlf() {
+        base_test("a,b\r\nc,d\r\n", "\r\n", vec!["a,b", "c,d", ""]);
+    }
+
+    #[test]
+    fn test_03_record_separator_character() {
+        base_test("a\x1fb\x1ec\x1fd", "\x1e", vec!["a\x1fb", "c\x1fd"]);
+    }
+
+    #[test]
+    fn test_04_multi_character_separator() {
+        base_test(
+            "name: a\nid: 1\n---\nname: b\nid: 2\n",
+            "---\n",
+            vec!["name: a\nid: 1\n", "name: b\nid: 2\n"],
+        );
+    }
+
+    #[test]
+    fn test_05_paragraphs() {
+        base_test("\na\nb\n\n\n\nc\n\nd\ne\n", "", vec!["a\nb", "c", "d\ne"]);
+    }
+
+    #[test]
+    fn test_06_empty_content() {
+        base_test("", "", vec![]);
+        base_test("", "\n", vec![""]);
+    }
+
+    #[test]
+    fn test_07_multibyte_characters() {
+        base_test("ñ│é\n│ñ", "│", vec!["ñ", "é\n", "ñ"]);
+    }
+
+    fn base_test(content: &str, separator: &str, expected: Vec<&str>) {
+        let separator = RecordSeparator::new(separator);
+
+        assert_eq!(expected, split_records(content, &separator));
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_remove_carriage_returns {
+    use super::{remove_carriage_returns, CrlfPolicy};
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_01_strip() {
+        base_test(
+            &["a,b\r", "c,d\r", ""],
+            CrlfPolicy::Strip,
+            vec!["a,b", "c,d", ""],
+            vec![false, false, false],
+        );
+    }
+
+    #[test]
+    fn test_02_keep() {
+        base_test(
+            &["a,b\r", "c,d"],
+            CrlfPolicy::Keep,
+            vec!["a,b", "c,d"],
+            vec![true, false],
+        );
+    }
+
+    #[test]
+    fn test_03_auto_with_crlf() {
+        base_test(
+            &["a,b\r", "c,d\r"],
+            CrlfPolicy::Auto,
+            vec!["a,b", "c,d"],
+            vec![true, true],
+        );
+    }
+
+    #[test]
+    fn test_04_auto_without_crlf() {
+        base_test(
+            &["a,b", "c,d\r"],
+            CrlfPolicy::Auto,
+            vec!["a,b", "c,d\r"],
+            vec![false, false],
+        );
+    }
+
+    #[test]
+    fn test_05_only_one_carriage_return_is_removed() {
+        base_test(&["a,b\r\r"], CrlfPolicy::Strip, vec!["a,b\r"], vec![false]);
+    }
+
+    #[test]
+    fn test_06_owned_records() {
+        let mut records = vec![Cow::Owned(String::from("a,b\r")), Cow::Borrowed("c,d\r")];
+
+        assert_eq!(
+            vec![true, true],
+            remove_carriage_returns(&mut records, CrlfPolicy::Keep)
+        );
+        assert_eq!(vec!["a,b", "c,d"], records);
+    }
+
+    #[test]
+    fn test_07_keep_without_carriage_returns() {
+        base_test(
+            &["a,b", "c,d", ""],
+            CrlfPolicy::Keep,
+            vec!["a,b", "c,d", ""],
+            vec![false, false, false],
+        );
+    }
+
+    #[test]
+    fn test_08_leave() {
+        base_test(
+            &["a,b\r", "c,d"],
+            CrlfPolicy::Leave,
+            vec!["a,b\r", "c,d"],
+            vec![false, false],
+        );
+    }
+
+    #[test]
+    fn test_09_resolve_auto() {
+        let with_crlf = [Cow::Borrowed("a,b\r"), Cow::Borrowed("c,d")];
+        let without_crlf = [Cow::Borrowed("a,b"), Cow::Borrowed("c,d\r")];
+
+        assert_eq!(CrlfPolicy::Keep, CrlfPolicy::Auto.resolve(&with_crlf));
+        assert_eq!(CrlfPolicy::Leave, CrlfPolicy::Auto.resolve(&without_crlf));
+        assert_eq!(CrlfPolicy::Auto, CrlfPolicy::Auto.resolve(&[]));
+        assert_eq!(CrlfPolicy::Strip, CrlfPolicy::Strip.resolve(&with_crlf));
+    }
+
+    fn base_test(
+        records: &[&str],
+        policy: CrlfPolicy,
+        expected: Vec<&str>,
+        expected_carriage_returns: Vec<bool>,
+    ) {
+        let mut records: Vec<Cow<str>> = records
+            .iter()
+            .map(|record| Cow::Borrowed(*record))
+            .collect();
+
+        let carriage_returns = remove_carriage_returns(&mut records, policy);
+
+        assert_eq!(expected, records);
+        assert_eq!(expected_carriage_returns, carriage_returns);
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_decode_records {
+    use super::{decode_records, RecordSeparator};
+    use crate::encoding::InvalidUtf8;
+
+    static CONTENT: &[u8] = b"a,b\nc\xe9,d\ne,f\xff\xfe\n";
+
+    #[test]
+    fn test_01_replace() {
+        base_test(
+            InvalidUtf8::Replace,
+            vec!["a,b", "c\u{fffd},d", "e,f\u{fffd}\u{fffd}", ""],
+        );
+    }
+
+    #[test]
+    fn test_02_skip_line() {
+        base_test(InvalidUtf8::SkipLine, vec!["a,b", ""]);
+    }
+
+    #[test]
+    fn test_03_bytes() {
+        base_test(
+            InvalidUtf8::Bytes,
+            vec!["a,b", "c\u{10ffe9},d", "e,f\u{10ffff}\u{10fffe}", ""],
+        );
+    }
+
+    #[test]
+    fn test_04_error() {
+        let separator = RecordSeparator::new("\n");
+
+        assert!(decode_records(CONTENT, None, &separator, InvalidUtf8::Error).is_err());
+    }
+
+    #[test]
+    fn test_05_valid_content() {
+        let separator = RecordSeparator::new("\n");
+
+        let (records, invalid_records) =
+            decode_records(b"a\nb", None, &separator, InvalidUtf8::SkipLine).unwrap();
+
+        assert_eq!(vec!["a", "b"], records);
+        assert!(invalid_records.is_empty());
+    }
+
+    fn base_test(invalid_utf8: InvalidUtf8, expected: Vec<&str>) {
+        let separator = RecordSeparator::new("\n");
+
+        let (records, invalid_records) =
+            decode_records(CONTENT, None, &separator, invalid_utf8).unwrap();
+
+        assert_eq!(expected, records);
+        assert_eq!(vec![2, 3], invalid_records);
+    }
+}
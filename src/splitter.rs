@@ -0,0 +1,397 @@
+//! Module, containing the ways a line is split into fields
+//!
+//! By default the fields are separated by a delimiter (`-d`).
+//! With `-d auto` the delimiter is detected from the first lines, among `,`, TAB, `;`, `|` and runs of spaces.
+//! Runs of spaces are one delimiter, and leading and trailing spaces are ignored.
+//!
+//! With `--widths` (or `--col-spec`) the line is fixed-width instead, and each field is a number of characters:
+//!     - `10,20,8` - three fields of 10, 20 and 8 characters, the rest of the line is ignored
+//!     - `10,20,8,*` - the same, with the rest of the line as a fourth field
+//!
+//! The fields are not trimmed, so padding is kept. Fields past the end of a short line are omitted.
+//! Single byte delimiters are searched with `memchr`.
+//! ```rust
+//! parse_widths("3,2,*").split("abcdefg") => ["abc", "de", "fg"]
+//! ```
+use std::collections::HashMap;
+use std::fs;
+
+// The candidates for `-d auto`, in the order they are preferred when they are equally good
+static CANDIDATES: [&str; 4] = [",", "\t", ";", "|"];
+
+/// Enum that represents how a line is split into fields
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FieldSplitter {
+    Delimiter(String),
+    SpaceRuns,
+    Widths {
+        widths: Vec<usize>,
+        is_keeping_rest: bool,
+    },
+}
+
+impl FieldSplitter {
+    pub fn split<'a>(&self, line: &'a str) -> Vec<&'a str> {
+        //! Split the line into its fields
+        self.split_first(line, usize::MAX)
+    }
+
+    pub fn split_first<'a>(&self, line: &'a str, limit: usize) -> Vec<&'a str> {
+        //! Split the first `limit` fields of the line, without searching the rest of it
+        match self {
+            FieldSplitter::Delimiter(delimiter) => split_on_delimiter(line, delimiter, limit),
+            FieldSplitter::SpaceRuns => line
+                .split(' ')
+                .filter(|field| !field.is_empty())
+                .take(limit)
+                .collect(),
+            FieldSplitter::Widths {
+                widths,
+                is_keeping_rest,
+            } => {
+                let mut fields = split_widths(line, widths, *is_keeping_rest);
+                fields.truncate(limit);
+                fields
+            }
+        }
+    }
+
+    pub fn is_delimited(&self, line: &str) -> bool {
+        //! Check if the line has more than one field. Fixed-width lines are always delimited.
+        match self {
+            FieldSplitter::Delimiter(delimiter) if delimiter.len() == 1 => {
+                memchr::memchr(delimiter.as_bytes()[0], line.as_bytes()).is_some()
+            }
+            FieldSplitter::Delimiter(delimiter) => line.contains(delimiter.as_str()),
+            FieldSplitter::SpaceRuns => line.trim_matches(' ').contains(' '),
+            FieldSplitter::Widths { .. } => true,
+        }
+    }
+}
+
+pub fn detect_delimiter<S: AsRef<str>>(lines: &[S]) -> FieldSplitter {
+    //! Choose the delimiter which splits the most `lines` into the same number of fields.
+    //! If several are equally consistent, the one with the most fields is chosen,
+    //! and if no delimiter splits the lines, TAB is used. Empty lines are ignored.
+    let candidates = CANDIDATES
+        .iter()
+        .map(|delimiter| FieldSplitter::Delimiter(delimiter.to_string()))
+        .chain(std::iter::once(FieldSplitter::SpaceRuns));
+
+    let mut best: Option<((usize, usize), FieldSplitter)> = None;
+    for candidate in candidates {
+        let mut frequencies: HashMap<usize, usize> = HashMap::new();
+        for line in lines
+            .iter()
+            .map(AsRef::as_ref)
+            .filter(|line| !line.is_empty())
+        {
+            *frequencies.entry(candidate.split(line).len()).or_default() += 1;
+        }
+
+        // The score is the number of lines with the most common number of fields, and that number
+        let score = match frequencies
+            .into_iter()
+            .filter(|(count, _)| *count > 1)
+            .max_by_key(|(count, frequency)| (*frequency, *count))
+        {
+            Some((count, frequency)) => (frequency, count),
+            None => continue,
+        };
+
+        if best
+            .as_ref()
+            .is_none_or(|(best_score, _)| score > *best_score)
+        {
+            best = Some((score, candidate));
+        }
+    }
+
+    best.map_or(
+        FieldSplitter::Delimiter(String::from("\t")),
+        |(_, splitter)| splitter,
+    )
+}
+
+pub fn describe(splitter: &FieldSplitter) -> String {
+    //! Describe the splitter for `--verbose`
+    match splitter {
+        FieldSplitter::Delimiter(delimiter) if delimiter == "\t" => String::from("delimiter TAB"),
+        FieldSplitter::Delimiter(delimiter) => format!("delimiter \"{}\"", delimiter),
+        FieldSplitter::SpaceRuns => String::from("runs of spaces as delimiter"),
+        FieldSplitter::Widths { .. } => String::from("fixed-width fields"),
+    }
+}
+
+pub fn parse_widths(input: &str) -> Result<FieldSplitter, String> {
+    //! Convert a comma separated list of widths into a `FieldSplitter`.
+    //! `*` can only be the last width.
+    let error_message = format!("Invalid widths \"{}\"", input);
+    let raw_widths: Vec<&str> = input.split(',').map(str::trim).collect();
+    let (raw_widths, is_keeping_rest) = match raw_widths.split_last() {
+        Some((&"*", rest)) => (rest, true),
+        _ => (raw_widths.as_slice(), false),
+    };
+
+    let mut widths = vec![];
+    for raw_width in raw_widths {
+        match raw_width.parse::<usize>() {
+            Ok(width) if width > 0 => widths.push(width),
+            _ => return Err(error_message),
+        }
+    }
+
+    if widths.is_empty() && !is_keeping_rest {
+        return Err(error_message);
+    }
+
+    Ok(FieldSplitter::Widths {
+        widths,
+        is_keeping_rest,
+    })
+}
+
+pub fn read_col_spec(path: &str) -> Result<FieldSplitter, String> {
+    //! Read the widths from a column specification file.
+    //!
+    //! The widths are separated by commas or line breaks, and everything after a `#` is a comment.
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => return Err(format!("Can't read column specification {}", path)),
+    };
+
+    let widths: Vec<&str> = content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .flat_map(|line| line.split(','))
+        .map(str::trim)
+        .filter(|width| !width.is_empty())
+        .collect();
+
+    parse_widths(&widths.join(","))
+}
+
+pub fn split_on_delimiter<'a>(line: &'a str, delimiter: &str, limit: usize) -> Vec<&'a str> {
+    //! Split the first `limit` fields of the line on the delimiter
+    if delimiter.len() != 1 {
+        return line.split(delimiter).take(limit).collect();
+    }
+
+    // An ASCII delimiter is searched with `memchr`
+    let mut fields = vec![];
+    let mut start = 0;
+    for end in memchr::memchr_iter(delimiter.as_bytes()[0], line.as_bytes()) {
+        if fields.len() == limit {
+            return fields;
+        }
+        fields.push(&line[start..end]);
+        start = end + 1;
+    }
+    if fields.len() < limit {
+        fields.push(&line[start..]);
+    }
+
+    fields
+}
+
+fn split_widths<'a>(line: &'a str, widths: &[usize], is_keeping_rest: bool) -> Vec<&'a str> {
+    //! Split the line into fields of the given number of characters
+    let mut fields = vec![];
+    let mut rest = line;
+
+    for width in widths {
+        if rest.is_empty() {
+            return fields;
+        }
+
+        let end = rest
+            .char_indices()
+            .nth(*width)
+            .map_or(rest.len(), |(index, _)| index);
+        fields.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+
+    if is_keeping_rest && !rest.is_empty() {
+        fields.push(rest);
+    }
+
+    fields
+}
+
+#[cfg(test)]
+mod unit_tests_parse_widths {
+    use super::{parse_widths, FieldSplitter};
+
+    #[test]
+    fn test_01_widths() {
+        let expected = FieldSplitter::Widths {
+            widths: vec![10, 20, 8],
+            is_keeping_rest: false,
+        };
+
+        assert_eq!(Ok(expected), parse_widths("10,20,8"));
+    }
+
+    #[test]
+    fn test_02_widths_with_rest() {
+        let expected = FieldSplitter::Widths {
+            widths: vec![10, 20, 8],
+            is_keeping_rest: true,
+        };
+
+        assert_eq!(Ok(expected), parse_widths("10, 20, 8, *"));
+    }
+
+    #[test]
+    fn test_03_rest_not_last() {
+        assert!(parse_widths("10,*,8").is_err());
+    }
+
+    #[test]
+    fn test_04_zero_width() {
+        assert!(parse_widths("10,0").is_err());
+    }
+
+    #[test]
+    fn test_05_not_a_number() {
+        assert!(parse_widths("10,a").is_err());
+        assert!(parse_widths("").is_err());
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_split {
+    use super::{parse_widths, FieldSplitter};
+
+    #[test]
+    fn test_01_exact_widths() {
+        base_test("3,2,2", "abcdefg", vec!["abc", "de", "fg"]);
+    }
+
+    #[test]
+    fn test_02_rest_is_ignored() {
+        base_test("3,2", "abcdefg", vec!["abc", "de"]);
+    }
+
+    #[test]
+    fn test_03_rest_is_kept() {
+        base_test("3,2,*", "abcdefg", vec!["abc", "de", "fg"]);
+    }
+
+    #[test]
+    fn test_04_short_line() {
+        base_test("3,3,3", "abcd", vec!["abc", "d"]);
+    }
+
+    #[test]
+    fn test_05_multibyte_characters() {
+        base_test("2,*", "ñañaña", vec!["ña", "ñaña"]);
+    }
+
+    #[test]
+    fn test_06_padding_is_kept() {
+        base_test("5,3", "Bob  42 ", vec!["Bob  ", "42 "]);
+    }
+
+    #[test]
+    fn test_07_single_byte_delimiter() {
+        let splitter = FieldSplitter::Delimiter(String::from(","));
+
+        assert_eq!(vec!["", "a", "ñ", ""], splitter.split(",a,ñ,"));
+        assert_eq!(vec!["abc"], splitter.split("abc"));
+    }
+
+    #[test]
+    fn test_08_first_fields() {
+        let splitter = FieldSplitter::Delimiter(String::from(","));
+
+        assert_eq!(vec!["a", "b"], splitter.split_first("a,b,c,d", 2));
+        assert_eq!(vec!["a", "b"], splitter.split_first("a,b", 5));
+        assert_eq!(
+            vec!["a", "b"],
+            FieldSplitter::SpaceRuns.split_first(" a  b c", 2)
+        );
+    }
+
+    fn base_test(widths: &str, line: &str, expected: Vec<&str>) {
+        let splitter = parse_widths(widths).unwrap();
+
+        assert_eq!(expected, splitter.split(line));
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_detect_delimiter {
+    use super::{detect_delimiter, FieldSplitter};
+
+    #[test]
+    fn test_01_comma() {
+        base_test(
+            &["Name,Age,City", "John Doe,35,New York"],
+            FieldSplitter::Delimiter(String::from(",")),
+        );
+    }
+
+    #[test]
+    fn test_02_most_consistent() {
+        base_test(
+            &["a;b,c;d", "e;f;g,h", "i;j;k"],
+            FieldSplitter::Delimiter(String::from(";")),
+        );
+    }
+
+    #[test]
+    fn test_03_most_fields() {
+        base_test(
+            &["a|b\tc|d", "e|f\tg|h"],
+            FieldSplitter::Delimiter(String::from("|")),
+        );
+    }
+
+    #[test]
+    fn test_04_space_runs() {
+        base_test(
+            &[
+                "  PID TTY          TIME CMD",
+                "    1 ?        00:00:01 init",
+            ],
+            FieldSplitter::SpaceRuns,
+        );
+    }
+
+    #[test]
+    fn test_05_empty_lines_are_ignored() {
+        base_test(
+            &["Name;Age", "", "John Doe;35", ""],
+            FieldSplitter::Delimiter(String::from(";")),
+        );
+    }
+
+    #[test]
+    fn test_06_inconsistent_lines() {
+        base_test(
+            &[
+                "USER PID COMMAND",
+                "root 1 init",
+                "root 2 sh -c true",
+                "root 3 cron",
+            ],
+            FieldSplitter::SpaceRuns,
+        );
+    }
+
+    #[test]
+    fn test_07_no_candidate() {
+        base_test(
+            &["abc", "def"],
+            FieldSplitter::Delimiter(String::from("\t")),
+        );
+    }
+
+    fn base_test(lines: &[&str], expected: FieldSplitter) {
+        let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+
+        assert_eq!(expected, detect_delimiter(&lines));
+    }
+}
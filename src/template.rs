@@ -0,0 +1,267 @@
+//! Module, containing the output templates used by `--format`
+//!
+//! A template is text with placeholders in braces, which are replaced by fields of the line:
+//!     - `{1}`, `{-1}` - a single field, using the same indexing as `-f`
+//!     - `{2:5}`, `{0,3}` - any LIST, with the fields joined by the output delimiter
+//!     - `{Name}` - in header mode, the field under the column `Name`
+//!
+//! In header mode, a column name takes priority over a LIST, so a column named `end` or `mid` is selected with `{end}` or `{mid}`.
+//!
+//! `{{` and `}}` are written as `{` and `}`.
+//! ```rust
+//! parse_template("{1} <{3}>").render(["1", "John Doe", "28", "john@example.com"], ",") => "John Doe <john@example.com>"
+//! ```
+use crate::cut::resolve_indexes;
+use crate::range_parser::{parse_range_list, RangeList};
+
+/// Struct that represents a parsed `--format` template
+#[derive(Debug, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum TemplatePart {
+    Text(String),
+    // The placeholder, which is a valid LIST, and its parsed ranges
+    Fields(String, RangeList),
+    Column(String),
+}
+
+impl Template {
+    pub fn resolve_names(&mut self, header: &[&str]) -> Result<(), String> {
+        //! Replace the column names in the placeholders with their index in the `header`.
+        //! A placeholder which is both a LIST and a column name selects the column.
+        for part in self.parts.iter_mut() {
+            let (name, is_list) = match part {
+                TemplatePart::Fields(placeholder, _) => (placeholder.clone(), true),
+                TemplatePart::Column(name) => (name.clone(), false),
+                TemplatePart::Text(_) => continue,
+            };
+
+            match header.iter().position(|column| *column == name) {
+                Some(index) => {
+                    *part = TemplatePart::Fields(name, parse_range_list(&index.to_string())?)
+                }
+                None if is_list => {}
+                None => return Err(format!("Unknown column \"{}\"", name)),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn has_names(&self) -> bool {
+        //! Check if any of the placeholders refers to a field by its column name
+        self.parts
+            .iter()
+            .any(|part| matches!(part, TemplatePart::Column(_)))
+    }
+
+    pub fn render(&self, fields: &[&str], output_delimiter: &str) -> String {
+        //! Replace the placeholders with the fields of the line.
+        //! Fields out of bounds are replaced with an empty string.
+        let n = fields.len();
+        let find_match =
+            |pattern: &regex::Regex| fields.iter().position(|field| pattern.is_match(field));
+        let mut result = String::new();
+
+        for part in &self.parts {
+            match part {
+                TemplatePart::Text(text) => result.push_str(text),
+                TemplatePart::Fields(_, ranges) => {
+                    let selected: Vec<&str> = ranges
+                        .resolve(n, &find_match)
+                        .into_iter()
+                        .flat_map(|range| resolve_indexes(range, n as i32, false))
+                        .filter_map(|index| fields.get(index).copied())
+                        .collect();
+                    result.push_str(&selected.join(output_delimiter));
+                }
+                TemplatePart::Column(_) => {}
+            }
+        }
+
+        result
+    }
+}
+
+pub fn parse_template(input: &str) -> Result<Template, String> {
+    //! Convert a `--format` template into a `Template`.
+    //!
+    //! A placeholder that is a valid LIST selects fields, otherwise it is a column name.
+    //! The LIST is kept with the placeholder, in case the header has a column with the same name.
+    let error_message = format!("Invalid format \"{}\"", input);
+    let mut parts = vec![];
+    let mut text = String::new();
+    let mut characters = input.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        match character {
+            '{' if characters.peek() == Some(&'{') => {
+                characters.next();
+                text.push('{');
+            }
+            '}' if characters.peek() == Some(&'}') => {
+                characters.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match characters.next() {
+                        Some('}') => break,
+                        Some(character) => placeholder.push(character),
+                        None => return Err(error_message),
+                    }
+                }
+
+                if placeholder.is_empty() {
+                    return Err(error_message);
+                }
+                if !text.is_empty() {
+                    parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                }
+                match parse_range_list(&placeholder) {
+                    Ok(ranges) => parts.push(TemplatePart::Fields(placeholder, ranges)),
+                    Err(_) => parts.push(TemplatePart::Column(placeholder)),
+                }
+            }
+            '}' => return Err(error_message),
+            _ => text.push(character),
+        }
+    }
+
+    if !text.is_empty() {
+        parts.push(TemplatePart::Text(text));
+    }
+
+    Ok(Template { parts })
+}
+
+#[cfg(test)]
+mod unit_tests_parse_template {
+    use super::{parse_template, Template, TemplatePart};
+    use crate::range_parser::parse_range_list;
+
+    #[test]
+    fn test_01_fields_and_text() {
+        let expected = Template {
+            parts: vec![
+                fields("1"),
+                TemplatePart::Text(String::from(" <")),
+                fields("-1"),
+                TemplatePart::Text(String::from(">")),
+            ],
+        };
+
+        assert_eq!(Ok(expected), parse_template("{1} <{-1}>"));
+    }
+
+    #[test]
+    fn test_02_column_name() {
+        let expected = Template {
+            parts: vec![TemplatePart::Column(String::from("Name"))],
+        };
+
+        assert_eq!(Ok(expected), parse_template("{Name}"));
+    }
+
+    #[test]
+    fn test_03_escaped_braces() {
+        let expected = Template {
+            parts: vec![
+                TemplatePart::Text(String::from("{")),
+                fields("0"),
+                TemplatePart::Text(String::from("}")),
+            ],
+        };
+
+        assert_eq!(Ok(expected), parse_template("{{{0}}}"));
+    }
+
+    #[test]
+    fn test_04_unclosed_placeholder() {
+        assert!(parse_template("{1} <{3").is_err());
+    }
+
+    #[test]
+    fn test_05_empty_placeholder() {
+        assert!(parse_template("{}").is_err());
+    }
+
+    #[test]
+    fn test_06_unopened_placeholder() {
+        assert!(parse_template("1}").is_err());
+    }
+
+    fn fields(list: &str) -> TemplatePart {
+        TemplatePart::Fields(String::from(list), parse_range_list(list).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod unit_tests_render_template {
+    use super::parse_template;
+
+    static FIELDS: [&str; 5] = ["1", "John Doe", "28", "john.doe@example.com", "New York"];
+
+    #[test]
+    fn test_01_single_fields() {
+        base_test("{1} <{3}>", "John Doe <john.doe@example.com>");
+    }
+
+    #[test]
+    fn test_02_negative_index() {
+        base_test("{1} lives in {-1}", "John Doe lives in New York");
+    }
+
+    #[test]
+    fn test_03_range_joined_with_output_delimiter() {
+        base_test("[{2:4}]", "[28;john.doe@example.com]");
+    }
+
+    #[test]
+    fn test_04_out_of_bounds() {
+        base_test("{1}:{10}", "John Doe:");
+    }
+
+    #[test]
+    fn test_05_column_names() {
+        let mut template = parse_template("{Name} ({Age})").unwrap();
+        template
+            .resolve_names(&["ID", "Name", "Age", "Email", "City"])
+            .unwrap();
+
+        assert_eq!("John Doe (28)", template.render(&FIELDS, ";"));
+    }
+
+    #[test]
+    fn test_06_unknown_column_name() {
+        let mut template = parse_template("{Height}").unwrap();
+
+        assert!(template.resolve_names(&["ID", "Name"]).is_err());
+    }
+
+    #[test]
+    fn test_07_column_names_take_priority_over_lists() {
+        let mut template = parse_template("{end}-{mid}-{1}").unwrap();
+        template.resolve_names(&["mid", "Name", "end"]).unwrap();
+
+        assert_eq!("28-1-John Doe", template.render(&FIELDS, ";"));
+    }
+
+    #[test]
+    fn test_08_lists_without_matching_column() {
+        let mut template = parse_template("{end-1}").unwrap();
+        template.resolve_names(&["ID", "Name"]).unwrap();
+
+        assert_eq!("New York", template.render(&FIELDS, ";"));
+    }
+
+    fn base_test(input: &str, expected: &str) {
+        let template = parse_template(input).unwrap();
+
+        assert_eq!(expected, template.render(&FIELDS, ";"));
+    }
+}
@@ -0,0 +1,66 @@
+//! Helpers shared by the functional tests, which call the built sbcut binary
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+static SBCUT: &str = "./target/debug/sbcut";
+
+pub fn call_sbcut(arguments: Vec<&str>) -> Result<(String, String, i32), String> {
+    println!("sbcut: {}", arguments.join(" "));
+
+    call_command(SBCUT, arguments)
+}
+
+pub fn call_sbcut_with_input(
+    arguments: Vec<&str>,
+    input: &str,
+) -> Result<(String, String, i32), String> {
+    println!("sbcut: {}", arguments.join(" "));
+
+    call_command_with_input(SBCUT, arguments, input)
+}
+
+fn call_command(command: &str, arguments: Vec<&str>) -> Result<(String, String, i32), String> {
+    let command_call = Command::new(command).args(arguments).output();
+    match command_call {
+        Ok(output) => Ok((
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap(),
+            output.status.code().unwrap(),
+        )),
+        Err(_) => Err(String::from("Can't execute command")),
+    }
+}
+
+fn call_command_with_input(
+    command: &str,
+    arguments: Vec<&str>,
+    input: &str,
+) -> Result<(String, String, i32), String> {
+    let child = Command::new(command)
+        .args(arguments)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+
+    let mut child = match child {
+        Ok(child) => child,
+        Err(_) => return Err(String::from("Can't execute command")),
+    };
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    match child.wait_with_output() {
+        Ok(output) => Ok((
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap(),
+            output.status.code().unwrap(),
+        )),
+        Err(_) => Err(String::from("Can't execute command")),
+    }
+}
@@ -406,7 +406,6 @@ mod functional_tests_coreutils_cut_compatibility {
     }
     
     #[test]
-    #[ignore = "https://github.com/lyubolp/slightly-better-cut/issues/1"]
     fn test_04_5_complement_singe_start_range() {
         // Arrange
         let cut_arguments = vec![
@@ -435,7 +434,6 @@ mod functional_tests_coreutils_cut_compatibility {
     }
     
     #[test]
-    #[ignore = "https://github.com/lyubolp/slightly-better-cut/issues/1"]
     fn test_04_6_complement_single_single_start_range() {
         // Arrange
         let cut_arguments = vec![
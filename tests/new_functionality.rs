@@ -1,3 +1,5 @@
+mod common;
+
 #[cfg(test)]
 mod functional_tests_indexing {
     use std::process::Command;
@@ -585,6 +587,23 @@ mod functional_tests_indexing {
         assert_eq!(expected_output, actual_output.unwrap())
     }
     
+    #[test]
+    fn test_04_1_fields_range_selecting_nothing() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "9,0", SAMPLE_FILE];
+        let expected_output = (
+            String::from(",ID\n,1\n,2\n,3\n"),
+            String::from(""),
+            0,
+        );
+    
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+    
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+    
     fn call_sbcut(arguments: Vec<&str>) -> Result<(String, String, i32), String> {
         println!("sbcut: {}", arguments.join(" "));
     
@@ -606,3 +625,1906 @@ mod functional_tests_indexing {
     }
     
 }
+
+#[cfg(test)]
+mod functional_tests_where {
+    use crate::common::call_sbcut;
+
+    static SAMPLE_FILE: &str = "sample_bigger.csv";
+
+    #[test]
+    fn test_01_numeric_comparison() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "0,2", "-H", "--where", "2>=30", SAMPLE_FILE];
+        let expected_output = (String::from("ID,Age\n2,34\n3,45\n"), String::from(""), 0);
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_02_regex_on_negative_index() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "1", "--where", "-2~^Software", SAMPLE_FILE];
+        let expected_output = (String::from("John Doe\n"), String::from(""), 0);
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_03_column_name_with_header() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "-d",
+            ",",
+            "-f",
+            "1,-1",
+            "--header",
+            "--where",
+            "Name==Bob Johnson or Salary<80000",
+            SAMPLE_FILE,
+        ];
+        let expected_output = (
+            String::from("Name,Salary\nJane Smith,75000\nBob Johnson,90000\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_04_and() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "-d",
+            ",",
+            "-f",
+            "0",
+            "-H",
+            "--where",
+            "Age>30 and City!=Chicago",
+            SAMPLE_FILE,
+        ];
+        let expected_output = (String::from("ID\n2\n"), String::from(""), 0);
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_05_column_name_without_header() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "0", "--where", "Name==Bob", SAMPLE_FILE];
+        let expected_output = (
+            String::from(""),
+            String::from("sbcut: Column names in --where require --header\n"),
+            1,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_06_column_name_with_header_on_empty_input() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "0", "-H", "--where", "Name==Bob", "/dev/null"];
+        let expected_output = (String::from(""), String::from(""), 0);
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_symbolic_bounds {
+    use crate::common::call_sbcut_with_input;
+
+    static SAMPLE_FILE: &str = "sample_bigger.csv";
+
+    #[test]
+    fn test_01_fields_end_with_offset() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "end-2:", SAMPLE_FILE];
+        let expected_output = (
+            String::from("Occupation,Salary\nSoftware Engineer,80000\nData Analyst,75000\nProject Manager,90000\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, "");
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_02_fields_mid() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "mid", SAMPLE_FILE];
+        let expected_output = (
+            String::from("City\nNew York\nLos Angeles\nChicago\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, "");
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_03_fields_from_regex() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", " ", "-f", "/ERROR/:"];
+        let input = "2024-01-01 ERROR disk full\n2024-01-02 INFO ERROR retried\n2024-01-03 INFO ok\n";
+        let expected_output = (
+            String::from("ERROR disk full\nERROR retried\n\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, input);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_04_characters_between_regexes() {
+        // Arrange
+        let sbcut_arguments = vec!["-c", "/\\[/+1:/\\]/"];
+        let input = "[INFO] started\nlevel=[WARN]\n";
+        let expected_output = (String::from("INFO\nWARN\n"), String::from(""), 0);
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, input);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_05_fields_until_end() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "5..=end", SAMPLE_FILE];
+        let expected_output = (
+            String::from("Country,Occupation,Salary\nUSA,Software Engineer,80000\nUSA,Data Analyst,75000\nUSA,Project Manager,90000\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, "");
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_06_single_end() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "end", SAMPLE_FILE];
+        let expected_output = (
+            String::from("Salary\n80000\n75000\n90000\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, "");
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_presets {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    static SAMPLE_FILE: &str = "sample_bigger.csv";
+    static CONFIG: &str = "[presets.ids]\ndelimiter = \",\"\nfields = \"0:2\"\noutput_delimiter = \"|\"\n";
+
+    #[test]
+    fn test_01_preset_from_config_home() {
+        // Arrange
+        let config_home = create_config_home("sbcut_test_preset_01");
+        let sbcut_arguments = vec!["@ids", SAMPLE_FILE];
+        let expected_output = (
+            String::from("ID|Name\n1|John Doe\n2|Jane Smith\n3|Bob Johnson\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments, &config_home);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_02_explicit_options_take_precedence() {
+        // Arrange
+        let config_home = create_config_home("sbcut_test_preset_02");
+        let sbcut_arguments = vec!["@ids", "-f", "6:", "--output_delimiter", ";", SAMPLE_FILE];
+        let expected_output = (
+            String::from("Occupation;Salary\nSoftware Engineer;80000\nData Analyst;75000\nProject Manager;90000\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments, &config_home);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_03_unknown_preset() {
+        // Arrange
+        let config_home = create_config_home("sbcut_test_preset_03");
+        let sbcut_arguments = vec!["@unknown", SAMPLE_FILE];
+        let expected_output = (
+            String::from(""),
+            String::from("sbcut: Unknown preset \"unknown\"\n"),
+            1,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments, &config_home);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_04_at_sign_after_the_first_argument() {
+        // Arrange
+        let config_home = create_config_home("sbcut_test_preset_04");
+        let input_file = config_home.join("@data");
+        fs::write(&input_file, "a@b@c\n").unwrap();
+        let sbcut_arguments = vec!["-d", "@", "-f", "1", input_file.to_str().unwrap()];
+        let expected_output = (String::from("b\n"), String::from(""), 0);
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments, &config_home);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    fn create_config_home(name: &str) -> PathBuf {
+        let config_home = std::env::temp_dir().join(name);
+        fs::create_dir_all(config_home.join("sbcut")).unwrap();
+        fs::write(config_home.join("sbcut").join("config.toml"), CONFIG).unwrap();
+
+        config_home
+    }
+
+    fn call_sbcut(
+        arguments: Vec<&str>,
+        config_home: &PathBuf,
+    ) -> Result<(String, String, i32), String> {
+        println!("sbcut: {}", arguments.join(" "));
+
+        let command_call = Command::new("./target/debug/sbcut")
+            .args(arguments)
+            .env("XDG_CONFIG_HOME", config_home)
+            .output();
+        match command_call {
+            Ok(output) => Ok((
+                String::from_utf8(output.stdout).unwrap(),
+                String::from_utf8(output.stderr).unwrap(),
+                output.status.code().unwrap(),
+            )),
+            Err(_) => Err(String::from("Can't execute command")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_explain {
+    use crate::common::call_sbcut;
+
+    static SAMPLE_FILE: &str = "sample_bigger.csv";
+
+    #[test]
+    fn test_01_explain_first_line() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "-5:-1:-2", "--explain", SAMPLE_FILE];
+        let expected_output = (
+            String::from(
+                "Line 1: ID,Name,Age,Email,City,Country,Occupation,Salary
+  Items: 8
+  Range -5:-1:-2 => 3:7:-2
+    Indexes: [5, 3]
+    [5] Country
+    [3] Email
+  Output: Country,Email
+",
+            ),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_02_explain_all_lines() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "mid", "--explain=all", SAMPLE_FILE];
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments).unwrap();
+
+        // Assert
+        assert_eq!(4, actual_output.0.matches("Line ").count());
+        assert!(actual_output.0.contains("  Output: Chicago\n"));
+    }
+
+    #[test]
+    fn test_03_explain_complement() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "1,3", "--complement", "--explain", "sample.csv"];
+        let expected_output = (
+            String::from(
+                "Line 1: Name,Age,Salary,City,Email
+  Items: 5
+  Range 1:2:1 => 1:2:1
+  Range 3:4:1 => 3:4:1
+  Complement of the ranges
+    Indexes: [0, 2, 4]
+    [0] Name
+    [2] Salary
+    [4] Email
+  Output: Name,Salary,Email
+",
+            ),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_output_format {
+    use crate::common::call_sbcut;
+
+    static SAMPLE_FILE: &str = "sample_bigger.csv";
+
+    #[test]
+    fn test_01_jsonl() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "1,2", "--output-format=jsonl", SAMPLE_FILE];
+        let expected_output = (
+            String::from("[\"Name\",\"Age\"]\n[\"John Doe\",\"28\"]\n[\"Jane Smith\",\"34\"]\n[\"Bob Johnson\",\"45\"]\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_02_jsonl_with_header() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "-d",
+            ",",
+            "-f",
+            "1,-1",
+            "--header",
+            "--output-format=jsonl",
+            SAMPLE_FILE,
+        ];
+        let expected_output = (
+            String::from("{\"Name\":\"John Doe\",\"Salary\":\"80000\"}\n{\"Name\":\"Jane Smith\",\"Salary\":\"75000\"}\n{\"Name\":\"Bob Johnson\",\"Salary\":\"90000\"}\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_03_csv_quotes_fields() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", " ", "-f", "0,1", "--output-format=csv", SAMPLE_FILE];
+        let expected_output = (
+            String::from("\"ID,Name,Age,Email,City,Country,Occupation,Salary\",\n\"1,John\",\"Doe,28,john.doe@example.com,New\"\n\"2,Jane\",\"Smith,34,jane.smith@example.com,Los\"\n\"3,Bob\",\"Johnson,45,bob.johnson@example.com,Chicago,USA,Project\"\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_04_tsv() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "1,2", "--output-format=tsv", SAMPLE_FILE];
+        let expected_output = (
+            String::from("Name\tAge\nJohn Doe\t28\nJane Smith\t34\nBob Johnson\t45\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_05_table() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "0,1,-2", "--output-format=table", SAMPLE_FILE];
+        let expected_output = (
+            String::from("ID  Name         Occupation\n1   John Doe     Software Engineer\n2   Jane Smith   Data Analyst\n3   Bob Johnson  Project Manager\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_06_table_with_border_and_header() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "-d",
+            ",",
+            "-f",
+            "0,1",
+            "--header",
+            "--output-format=table",
+            "--table-border",
+            SAMPLE_FILE,
+        ];
+        let expected_output = (
+            String::from(
+                "┌────┬─────────────┐
+│ ID │ Name        │
+├────┼─────────────┤
+│ 1  │ John Doe    │
+│ 2  │ Jane Smith  │
+│ 3  │ Bob Johnson │
+└────┴─────────────┘
+",
+            ),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_07_table_options_without_table() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "0,1", "--output-format=csv", "--table-border", SAMPLE_FILE];
+        let expected_output = (
+            String::from(""),
+            String::from("sbcut: --table-border requires --output-format=table\n"),
+            1,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_08_markdown_with_header() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "0,1", "-H", "--output-format=markdown", SAMPLE_FILE];
+        let expected_output = (
+            String::from("| ID | Name |\n| --- | --- |\n| 1 | John Doe |\n| 2 | Jane Smith |\n| 3 | Bob Johnson |\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_09_html_with_header() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "0,1", "-H", "--where", "0<3", "--output-format=html", SAMPLE_FILE];
+        let expected_output = (
+            String::from(
+                "<table>
+  <thead>
+    <tr><th>ID</th><th>Name</th></tr>
+  </thead>
+  <tbody>
+    <tr><td>1</td><td>John Doe</td></tr>
+    <tr><td>2</td><td>Jane Smith</td></tr>
+  </tbody>
+</table>
+",
+            ),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_json_input {
+    use crate::common::call_sbcut;
+
+    static SAMPLE_FILE: &str = "sample.jsonl";
+
+    #[test]
+    fn test_01_keys_and_paths() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "--json",
+            "-f",
+            "ts,user.id,req.headers.host",
+            "--output_delimiter",
+            ",",
+            SAMPLE_FILE,
+        ];
+        let expected_output = (
+            String::from("2024-03-01T10:00:00Z,1,example.com\n2024-03-01T10:00:05Z,2,api.example.com\n2024-03-01T10:01:12Z,3,\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_02_default_output_delimiter() {
+        // Arrange
+        let sbcut_arguments = vec!["--json", "-f", "level,req.method", SAMPLE_FILE];
+        let expected_output = (
+            String::from("info\tGET\nerror\tPOST\ninfo\tGET\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_03_invalid_line() {
+        // Arrange
+        let sbcut_arguments = vec!["--json", "-f", "0", "sample.csv"];
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments).unwrap();
+
+        // Assert
+        assert_eq!("", actual_output.0);
+        assert!(actual_output.1.starts_with("sbcut: line 1: Invalid JSON"));
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_format {
+    use crate::common::call_sbcut;
+
+    static SAMPLE_FILE: &str = "sample.csv";
+
+    #[test]
+    fn test_01_single_fields() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "--format", "{0} <{-1}>", SAMPLE_FILE];
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments).unwrap();
+
+        // Assert
+        let lines: Vec<&str> = actual_output.0.lines().take(2).collect();
+        assert_eq!(vec!["Name <Email>", "John Doe <john.doe@example.com>"], lines);
+        assert_eq!(0, actual_output.2);
+    }
+
+    #[test]
+    fn test_02_header_names_and_range() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "-d",
+            ",",
+            "-H",
+            "--where",
+            "Age<28",
+            "--format",
+            "{Name} ({1:3})",
+            "--output_delimiter",
+            " / ",
+            SAMPLE_FILE,
+        ];
+        let expected_output = (
+            String::from("Name (Age / Salary)\nDavid Lee (27 / 65000)\nTom Brown (25 / 50000)\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_03_names_without_header() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "--format", "{Name}", SAMPLE_FILE];
+        let expected_output = (
+            String::from(""),
+            String::from("sbcut: Column names in --format require --header\n"),
+            1,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_fixed_width {
+    use crate::common::call_sbcut;
+
+    static SAMPLE_FILE: &str = "sample_fixed_width.txt";
+
+    #[test]
+    fn test_01_widths() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "--widths",
+            "10,20,8,*",
+            "-f",
+            "0,-1",
+            "--output_delimiter",
+            ",",
+            SAMPLE_FILE,
+        ];
+        let expected_output = (
+            String::from("ACC001    ,USD\nACC002    ,EUR\nACC003    ,USD\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_02_widths_complement() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "--widths",
+            "10,20,8",
+            "-f",
+            "1",
+            "--complement",
+            "--output_delimiter",
+            "|",
+            SAMPLE_FILE,
+        ];
+        let expected_output = (
+            String::from("ACC001    |00012050\nACC002    |00009900\nACC003    |00150000\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_03_col_spec() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "--col-spec",
+            "sample_fixed_width.spec",
+            "-f",
+            "2:",
+            "--where",
+            "2>10000",
+            SAMPLE_FILE,
+        ];
+        let expected_output = (
+            String::from("00012050\tUSD\n00150000\tUSD\n"),
+            String::from(""),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_04_invalid_widths() {
+        // Arrange
+        let sbcut_arguments = vec!["--widths", "10,*,8", "-f", "0", SAMPLE_FILE];
+        let expected_output = (
+            String::from(""),
+            String::from("sbcut: Invalid widths \"10,*,8\"\n"),
+            1,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_auto_delimiter {
+    use crate::common::call_sbcut_with_input;
+
+    #[test]
+    fn test_01_comma() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", "auto", "-f", "0,-1", "sample.csv"];
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, "").unwrap();
+
+        // Assert
+        let lines: Vec<&str> = actual_output.0.lines().take(2).collect();
+        assert_eq!(vec!["Name,Email", "John Doe,john.doe@example.com"], lines);
+        assert_eq!("", actual_output.1);
+    }
+
+    #[test]
+    fn test_02_verbose() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", "auto", "--verbose", "-f", "1", "sample.tsv"];
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, "").unwrap();
+
+        // Assert
+        assert!(actual_output.0.starts_with("Age\n35\n"));
+        assert_eq!("sbcut: using delimiter TAB\n", actual_output.1);
+    }
+
+    #[test]
+    fn test_03_space_runs() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", "auto", "-v", "-f", "1:"];
+        let input = "  PID TTY          TIME CMD\n    1 ?        00:00:01 init\n   42 pts/0    00:00:00 bash\n";
+        let expected_output = (
+            String::from("TTY TIME CMD\n? 00:00:01 init\npts/0 00:00:00 bash\n"),
+            String::from("sbcut: using runs of spaces as delimiter\n"),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, input);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_escaped_delimiters {
+    use crate::common::call_sbcut_with_input;
+
+    #[test]
+    fn test_01_tab_escape() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "-d",
+            "\\t",
+            "-f",
+            "0,-1",
+            "--output_delimiter",
+            "\\x1f",
+            "sample.tsv",
+        ];
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, "").unwrap();
+
+        // Assert
+        assert!(actual_output
+            .0
+            .starts_with("Name\u{1f}Email\nJohn Doe\u{1f}john.doe@example.com\n"));
+        assert_eq!(0, actual_output.2);
+    }
+
+    #[test]
+    fn test_02_unicode_escape() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", "\\u{2502}", "-f", "1:", "--output_delimiter", ","];
+        let input = "a│b│c\nd│e│f\n";
+        let expected_output = (String::from("b,c\ne,f\n"), String::from(""), 0);
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, input);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_03_literal_delimiter() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", "\\t", "--literal-delimiter", "-f", "0"];
+        let input = "a\\tb\tc\n";
+        let expected_output = (String::from("a\n"), String::from(""), 0);
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, input);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_04_invalid_escape() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", "\\q", "-f", "0"];
+        let expected_output = (
+            String::from(""),
+            String::from("sbcut: Invalid escape sequence \"\\q\"\n"),
+            1,
+        );
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, "a\n");
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_record_separator {
+    use crate::common::call_sbcut_with_input;
+
+    #[test]
+    fn test_01_multi_character_separator() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "--record-separator",
+            "---\\n",
+            "-d",
+            "\\n",
+            "-f",
+            "1",
+            "--output-record-separator",
+            ";",
+        ];
+        let input = "name: a\nid: 1\n---\nname: b\nid: 2\n";
+        let expected_output = (String::from("id: 1;id: 2;"), String::from(""), 0);
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, input);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_02_paragraph_mode() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "--record-separator",
+            "",
+            "-d",
+            "\\n",
+            "-f",
+            "0,-1",
+            "--output_delimiter",
+            ": ",
+        ];
+        let input = "\nBob\nBuilder\n42\n\n\nAnn\n37\n";
+        let expected_output = (String::from("Bob: 42\nAnn: 37\n"), String::from(""), 0);
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, input);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_03_zero_terminated_output() {
+        // Arrange
+        let sbcut_arguments = vec!["-z", "-d", ",", "-f", "1"];
+        let input = "a,b\0c,d\0";
+        let expected_output = (String::from("b\0d\0"), String::from(""), 0);
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, input);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_crlf {
+    use crate::common::call_sbcut;
+
+    static SAMPLE_FILE: &str = "sample_crlf.csv";
+
+    #[test]
+    fn test_01_auto_keeps_line_endings() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "-1", SAMPLE_FILE];
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments).unwrap();
+
+        // Assert
+        assert!(actual_output
+            .0
+            .starts_with("Email\r\njohn.doe@example.com\r\n"));
+        assert_eq!(0, actual_output.2);
+    }
+
+    #[test]
+    fn test_02_strip() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "2:", "--crlf", "strip", SAMPLE_FILE];
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments).unwrap();
+
+        // Assert
+        assert!(actual_output
+            .0
+            .starts_with("Salary,City,Email\n75000,New York,john.doe@example.com\n"));
+        assert!(!actual_output.0.contains('\r'));
+    }
+
+    #[test]
+    fn test_03_characters_exclude_carriage_return() {
+        // Arrange
+        let sbcut_arguments = vec!["-c", "-1", "--crlf=strip", SAMPLE_FILE];
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments).unwrap();
+
+        // Assert
+        assert!(actual_output.0.starts_with("l\nm\n"));
+    }
+
+    #[test]
+    fn test_04_same_fields_as_lf_file() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "-1", "--crlf=strip", SAMPLE_FILE];
+
+        // Act
+        let crlf_output = call_sbcut(sbcut_arguments);
+        let lf_output = call_sbcut(vec!["-d", ",", "-f", "-1", "sample.csv"]);
+
+        // Assert
+        assert_eq!(lf_output, crlf_output)
+    }
+
+    #[test]
+    fn test_05_keep_on_lf_file() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "-1", "--crlf=keep", "sample.csv"];
+
+        // Act
+        let keep_output = call_sbcut(sbcut_arguments);
+        let lf_output = call_sbcut(vec!["-d", ",", "-f", "-1", "sample.csv"]);
+
+        // Assert
+        assert_eq!(lf_output, keep_output)
+    }
+
+    #[test]
+    fn test_06_keep_in_csv_output() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "-d",
+            ",",
+            "-f",
+            "-1",
+            "--crlf=keep",
+            "--output-format=csv",
+            SAMPLE_FILE,
+        ];
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments).unwrap();
+
+        // Assert
+        assert!(actual_output
+            .0
+            .starts_with("Email\r\njohn.doe@example.com\r\n"));
+        assert_eq!(0, actual_output.2);
+    }
+
+    #[test]
+    fn test_07_keep_in_table_output() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "-d",
+            ",",
+            "-f",
+            "0",
+            "--crlf=keep",
+            "--output-format=table",
+            SAMPLE_FILE,
+        ];
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments).unwrap();
+
+        // Assert
+        assert!(actual_output.0.starts_with("Name\r\nJohn Doe\r\n"));
+        assert_eq!(0, actual_output.2);
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_decompression {
+    use crate::common::call_sbcut;
+
+    #[test]
+    fn test_01_compressed_formats() {
+        // Arrange
+        let expected_output = call_sbcut(vec!["-d", ",", "-f", "0,-1", "sample.csv"]);
+
+        for compressed_file in [
+            "sample.csv.gz",
+            "sample.csv.zst",
+            "sample.csv.bz2",
+            "sample.csv.xz",
+        ] {
+            let sbcut_arguments = vec!["-d", ",", "-f", "0,-1", compressed_file];
+
+            // Act
+            let actual_output = call_sbcut(sbcut_arguments);
+
+            // Assert
+            assert_eq!(expected_output, actual_output)
+        }
+    }
+
+    #[test]
+    fn test_02_decompress_none() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "0", "--decompress=none", "sample.csv.gz"];
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments).unwrap();
+
+        // Assert
+        assert_eq!("", actual_output.0);
+        assert!(actual_output.1.starts_with("sbcut: Input is not valid UTF-8"));
+        assert_eq!(1, actual_output.2);
+    }
+
+    #[test]
+    fn test_03_missing_file() {
+        // Arrange
+        let sbcut_arguments = vec!["-f", "0", "missing.csv.gz"];
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments).unwrap();
+
+        // Assert
+        assert!(actual_output.1.starts_with("sbcut: Can't open missing.csv.gz"));
+        assert_eq!(1, actual_output.2);
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_encoding {
+    use std::process::Command;
+    use crate::common::call_sbcut;
+
+    #[test]
+    fn test_01_invalid_utf8_without_encoding() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ";", "-f", "1", "sample_latin1.csv"];
+        let expected_output = (
+            String::from(""),
+            String::from("sbcut: Input is not valid UTF-8 at byte 13, use --encoding to set its encoding or --invalid-utf8 to handle it\n"),
+            1,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_02_encoding() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "-d",
+            ";",
+            "-f",
+            "1",
+            "--encoding",
+            "windows-1252",
+            "sample_latin1.csv",
+        ];
+        let expected_output = (String::from("Ville\nMontréal\nQuébec\n"), String::from(""), 0);
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_03_utf16_byte_order_mark() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "-1", "sample_utf16.csv"];
+        let expected_output = (String::from("City\nMontréal\nKöln\n"), String::from(""), 0);
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_04_output_encoding() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "-c",
+            "0:4",
+            "--encoding",
+            "latin1",
+            "--output-encoding",
+            "latin1",
+            "sample_latin1.csv",
+        ];
+
+        // Act
+        let actual_output = Command::new("./target/debug/sbcut")
+            .args(sbcut_arguments)
+            .output()
+            .unwrap();
+
+        // Assert
+        assert_eq!(b"Nom;\nJos\xe9\nFran\n".to_vec(), actual_output.stdout);
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_invalid_utf8 {
+    use std::process::Command;
+    use crate::common::{call_sbcut, call_sbcut_with_input};
+
+    static SAMPLE_FILE: &str = "sample_latin1.csv";
+
+    #[test]
+    fn test_01_replace() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ";", "-f", "0", "--invalid-utf8=replace", SAMPLE_FILE];
+        let expected_output = (
+            String::from("Nom\nJos\u{fffd}\nFran\u{fffd}ois\n"),
+            String::from("sbcut: 2 lines with invalid UTF-8\n"),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_02_skip_line() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ";", "-f", "1", "--invalid-utf8=skip-line", SAMPLE_FILE];
+        let expected_output = (
+            String::from("Ville\n"),
+            String::from("sbcut: line 2: invalid UTF-8, skipped\nsbcut: line 3: invalid UTF-8, skipped\nsbcut: 2 lines with invalid UTF-8\n"),
+            0,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_03_bytes() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ";", "-f", "1", "--invalid-utf8=bytes", SAMPLE_FILE];
+
+        // Act
+        let output = Command::new("./target/debug/sbcut")
+            .args(sbcut_arguments)
+            .output()
+            .unwrap();
+
+        // Assert
+        assert_eq!(b"Ville\nMontr\xe9al\nQu\xe9bec\n".to_vec(), output.stdout);
+        assert_eq!(b"sbcut: 2 lines with invalid UTF-8\n".to_vec(), output.stderr);
+    }
+
+    #[test]
+    fn test_04_error() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ";", "-f", "1", "--invalid-utf8=error", SAMPLE_FILE];
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments).unwrap();
+
+        // Assert
+        assert_eq!("", actual_output.0);
+        assert!(actual_output.1.starts_with("sbcut: Input is not valid UTF-8"));
+        assert_eq!(1, actual_output.2);
+    }
+
+    #[test]
+    fn test_05_bytes_counted_one_by_one() {
+        // Arrange
+        let sbcut_arguments = vec!["-b", "3:6", "--invalid-utf8=bytes", SAMPLE_FILE];
+
+        // Act
+        let output = Command::new("./target/debug/sbcut")
+            .args(sbcut_arguments)
+            .output()
+            .unwrap();
+
+        // Assert
+        assert_eq!(b";Vi\n\xe9;M\nn\xe7o\n".to_vec(), output.stdout);
+    }
+
+    #[test]
+    fn test_06_bytes_with_output_encoding() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "-d",
+            ";",
+            "-f",
+            "1",
+            "--invalid-utf8=bytes",
+            "--output-encoding=latin1",
+            SAMPLE_FILE,
+        ];
+        let expected_output = (
+            String::from(""),
+            String::from("sbcut: --invalid-utf8=bytes can't be used with --output-encoding\n"),
+            1,
+        );
+
+        // Act
+        let actual_output = call_sbcut(sbcut_arguments);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap())
+    }
+
+    #[test]
+    fn test_07_valid_last_code_point() {
+        // Arrange
+        let input = "a\u{10FFFF}b\n";
+        let expected_outputs = [
+            (vec!["-c", "0:3", "--invalid-utf8=bytes"], "a\u{10FFFF}b\n"),
+            (vec!["-b", "5", "--invalid-utf8=bytes"], "b\n"),
+            (vec!["-b", "5"], "b\n"),
+        ];
+
+        for (sbcut_arguments, expected_output) in expected_outputs {
+            // Act
+            let actual_output = call_sbcut_with_input(sbcut_arguments, input).unwrap();
+
+            // Assert
+            assert_eq!(expected_output, actual_output.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_threads {
+    use std::fs::File;
+    use std::io::{BufRead, BufReader, Write};
+    use std::process::{Command, Stdio};
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+    use crate::common::call_sbcut_with_input;
+
+    #[test]
+    fn test_01_same_output_as_one_thread() {
+        // Arrange
+        let input: String = (0..25_000)
+            .map(|index| format!("{},user{},{}\n", index, index % 100, index * 3))
+            .collect();
+        let sbcut_arguments = vec!["-d", ",", "-f", "-1,0", "-H", "--where", "1==user7"];
+
+        // Act
+        let expected_output = call_sbcut_with_input(sbcut_arguments.clone(), &input).unwrap();
+        let actual_output = call_sbcut_with_input([sbcut_arguments, vec!["-j", "4"]].concat(), &input);
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap());
+        assert!(expected_output.0.starts_with("0,0\n21,7\n321,107\n"));
+    }
+
+    #[test]
+    fn test_02_unsupported_output_format() {
+        // Arrange
+        let sbcut_arguments = vec![
+            "-d",
+            ",",
+            "-f",
+            "0",
+            "-j",
+            "2",
+            "--output-format",
+            "jsonl",
+            "-H",
+            "sample.csv",
+        ];
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, "").unwrap();
+
+        // Assert
+        assert!(actual_output.0.starts_with("{\"Name\":\"John Doe\"}\n"));
+        assert_eq!(0, actual_output.2);
+    }
+
+    #[test]
+    fn test_03_zero_threads() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "0", "-j", "0", "sample.csv"];
+
+        // Act
+        let actual_output = call_sbcut_with_input(sbcut_arguments, "").unwrap();
+
+        // Assert
+        assert_eq!(2, actual_output.2);
+    }
+
+    #[test]
+    fn test_04_stdin_cut_as_it_is_read() {
+        // Arrange
+        let input: String = (0..20_000).map(|index| format!("{},a\n", index)).collect();
+        let mut child = Command::new("./target/debug/sbcut")
+            .args(["-d", ",", "-f", "0", "-j", "2"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut first_line = String::new();
+            BufReader::new(stdout).read_line(&mut first_line).unwrap();
+            sender.send(first_line).unwrap();
+        });
+
+        // Act
+        let mut stdin = child.stdin.take().unwrap();
+        stdin.write_all(input.as_bytes()).unwrap();
+        // The input is still open, so the first line is only written if the batch is cut before the end
+        let first_line = receiver.recv_timeout(Duration::from_secs(5));
+        drop(stdin);
+        child.wait().unwrap();
+
+        // Assert
+        assert_eq!(Ok(String::from("0\n")), first_line);
+    }
+
+    #[test]
+    fn test_05_utf16_stdin_cut_as_it_is_read() {
+        // Arrange
+        let expected_output = String::from("City\nMontréal\nKöln\n");
+
+        for streaming_arguments in [vec!["-j", "2"], vec!["--line-buffered"]] {
+            // Act
+            let output = Command::new("./target/debug/sbcut")
+                .args([vec!["-d", ",", "-f", "1"], streaming_arguments].concat())
+                .stdin(File::open("sample_utf16.csv").unwrap())
+                .output()
+                .unwrap();
+
+            // Assert
+            assert_eq!(expected_output, String::from_utf8(output.stdout).unwrap());
+        }
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_output_buffering {
+    use std::io::{BufRead, BufReader, Write};
+    use std::process::{ChildStdout, Command, Stdio};
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread;
+    use std::time::Duration;
+    use crate::common::call_sbcut;
+
+    static TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn test_01_broken_pipe() {
+        // Arrange
+        let input: String = (0..200_000)
+            .map(|index| format!("{},{}\n", index, index * 2))
+            .collect();
+        let mut child = Command::new("./target/debug/sbcut")
+            .args(["-d", ",", "-f", "1"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+
+        // Act
+        let mut first_line = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut first_line)
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+
+        // Assert
+        assert_eq!("0\n", first_line);
+        assert_eq!(Some(141), output.status.code());
+        assert_eq!("", String::from_utf8(output.stderr).unwrap());
+    }
+
+    #[test]
+    fn test_02_line_buffered() {
+        // Arrange
+        let sbcut_arguments = vec!["-d", ",", "-f", "0,-1", "sample.csv"];
+
+        // Act
+        let expected_output = call_sbcut(sbcut_arguments.clone()).unwrap();
+        let actual_output = call_sbcut([sbcut_arguments, vec!["--line-buffered"]].concat());
+
+        // Assert
+        assert_eq!(expected_output, actual_output.unwrap());
+    }
+
+    #[test]
+    fn test_03_line_buffered_stdin_written_per_record() {
+        // Arrange
+        let mut child = Command::new("./target/debug/sbcut")
+            .args(["-z", "-d", ",", "-f", "0", "--line-buffered"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdin = child.stdin.take().unwrap();
+        let records = read_records(child.stdout.take().unwrap());
+
+        // Act
+        // The input stays open, so each record is only received if it is written as soon as it is read
+        stdin.write_all(b"a,b\0").unwrap();
+        let first_record = records.recv_timeout(TIMEOUT);
+        stdin.write_all(b"c,d\0").unwrap();
+        let second_record = records.recv_timeout(TIMEOUT);
+        drop(stdin);
+        child.wait().unwrap();
+
+        // Assert
+        assert_eq!(Ok(b"a\0".to_vec()), first_record);
+        assert_eq!(Ok(b"c\0".to_vec()), second_record);
+    }
+
+    #[test]
+    fn test_04_line_buffered_carriage_returns_decided_once() {
+        // Arrange
+        let mut child = Command::new("./target/debug/sbcut")
+            .args(["-z", "-c", "-1", "--line-buffered"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdin = child.stdin.take().unwrap();
+        let records = read_records(child.stdout.take().unwrap());
+
+        // Act
+        // The first record has no carriage return, so the ones of the records read later are left as they are
+        stdin.write_all(b"ab\0").unwrap();
+        let first_record = records.recv_timeout(TIMEOUT);
+        stdin.write_all(b"cd\r\0ef\r\0").unwrap();
+        let later_records = [records.recv_timeout(TIMEOUT), records.recv_timeout(TIMEOUT)];
+        drop(stdin);
+        child.wait().unwrap();
+
+        // Assert
+        assert_eq!(Ok(b"b\0".to_vec()), first_record);
+        assert_eq!([Ok(b"\r\0".to_vec()), Ok(b"\r\0".to_vec())], later_records);
+    }
+
+    fn read_records(stdout: ChildStdout) -> Receiver<Vec<u8>> {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for record in BufReader::new(stdout).split(b'\0') {
+                let mut record = record.unwrap();
+                record.push(b'\0');
+                if sender.send(record).is_err() {
+                    break;
+                }
+            }
+        });
+        receiver
+    }
+}
+
+#[cfg(test)]
+mod functional_tests_follow {
+    use std::fs::{self, OpenOptions};
+    use std::io::{BufRead, BufReader, Write};
+    use std::process::{Child, ChildStdout, Command, Stdio};
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread;
+    use std::time::Duration;
+
+    static TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn test_01_new_records() {
+        // Arrange
+        let path = format!("{}/follow_new_records.log", env!("CARGO_TARGET_TMPDIR"));
+        fs::write(&path, "time,level,msg\n1,INFO,start\n").unwrap();
+        let mut child = spawn_sbcut(vec!["-F", "-d", ",", "-f", "2", "-H", &path]);
+        let lines = read_lines(child.stdout.take().unwrap());
+
+        // Act
+        let first_lines = receive(&lines, 2);
+        append(&path, "2,ERROR,bo");
+        thread::sleep(Duration::from_millis(300));
+        append(&path, "om\n3,INFO,done\n");
+        let new_lines = receive(&lines, 2);
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        // Assert
+        assert_eq!(vec!["msg", "start"], first_lines);
+        assert_eq!(vec!["boom", "done"], new_lines);
+    }
+
+    #[test]
+    fn test_02_rotation() {
+        // Arrange
+        let path = format!("{}/follow_rotation.log", env!("CARGO_TARGET_TMPDIR"));
+        fs::write(&path, "1,old\n").unwrap();
+        let mut child = spawn_sbcut(vec!["-F", "-d", ",", "-f", "1", &path]);
+        let lines = read_lines(child.stdout.take().unwrap());
+
+        // Act
+        let first_lines = receive(&lines, 1);
+        fs::rename(&path, format!("{}.1", path)).unwrap();
+        fs::write(&path, "2,new\n").unwrap();
+        let new_lines = receive(&lines, 1);
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        // Assert
+        assert_eq!(vec!["old"], first_lines);
+        assert_eq!(vec!["new"], new_lines);
+    }
+
+    #[test]
+    fn test_03_stdin() {
+        // Arrange
+        let sbcut_arguments = vec!["-F", "-d", ",", "-f", "1"];
+
+        // Act
+        let output = Command::new("./target/debug/sbcut")
+            .args(sbcut_arguments)
+            .output()
+            .unwrap();
+
+        // Assert
+        assert_eq!(Some(1), output.status.code());
+        assert_eq!(
+            "sbcut: --follow requires a FILE\n",
+            String::from_utf8(output.stderr).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_04_html() {
+        // Arrange
+        let path = format!("{}/follow_html.log", env!("CARGO_TARGET_TMPDIR"));
+        fs::write(&path, "1,a\n").unwrap();
+        let sbcut_arguments = vec!["-F", "-d", ",", "-f", "1", "--output-format=html", &path];
+
+        // Act
+        let output = Command::new("./target/debug/sbcut")
+            .args(sbcut_arguments)
+            .output()
+            .unwrap();
+
+        // Assert
+        assert_eq!(Some(1), output.status.code());
+        assert_eq!(
+            "sbcut: --follow can't be used with --output-format=html\n",
+            String::from_utf8(output.stderr).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_05_table_without_sample() {
+        // Arrange
+        let path = format!("{}/follow_table.log", env!("CARGO_TARGET_TMPDIR"));
+        fs::write(&path, "1,a\n").unwrap();
+        let sbcut_arguments = vec!["-F", "-d", ",", "-f", "1", "--output-format=table", &path];
+
+        // Act
+        let output = Command::new("./target/debug/sbcut")
+            .args(sbcut_arguments)
+            .output()
+            .unwrap();
+
+        // Assert
+        assert_eq!(Some(1), output.status.code());
+        assert_eq!(
+            "sbcut: --follow requires --table-sample with --output-format=table\n",
+            String::from_utf8(output.stderr).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_06_table_with_sample() {
+        // Arrange
+        let path = format!("{}/follow_table_sample.log", env!("CARGO_TARGET_TMPDIR"));
+        fs::write(&path, "1,a\n").unwrap();
+        let mut child = spawn_sbcut(vec![
+            "-F",
+            "-d",
+            ",",
+            "-f",
+            "0:",
+            "--output-format=table",
+            "--table-sample=1",
+            &path,
+        ]);
+        let lines = read_lines(child.stdout.take().unwrap());
+
+        // Act
+        let first_lines = receive(&lines, 1);
+        append(&path, "2,b\n");
+        let new_lines = receive(&lines, 1);
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        // Assert
+        assert_eq!(vec!["1  a"], first_lines);
+        assert_eq!(vec!["2  b"], new_lines);
+    }
+
+    #[test]
+    fn test_07_zero_terminated() {
+        // Arrange
+        let path = format!("{}/follow_zero_terminated.log", env!("CARGO_TARGET_TMPDIR"));
+        fs::write(&path, "1,a\0").unwrap();
+        let mut child = spawn_sbcut(vec!["-F", "-z", "-d", ",", "-f", "1", &path]);
+        let records = read_records(child.stdout.take().unwrap(), b'\0');
+
+        // Act
+        let first_records = receive(&records, 1);
+        append(&path, "2,b\0");
+        let new_records = receive(&records, 1);
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        // Assert
+        assert_eq!(vec!["a"], first_records);
+        assert_eq!(vec!["b"], new_records);
+    }
+
+    #[test]
+    fn test_08_utf16() {
+        // Arrange
+        let path = format!("{}/follow_utf16.log", env!("CARGO_TARGET_TMPDIR"));
+        fs::write(&path, b"\xff\xfea\x00,\x00b\x00\n\x00").unwrap();
+        let mut child = spawn_sbcut(vec!["-F", "-d", ",", "-f", "1", &path]);
+        let lines = read_lines(child.stdout.take().unwrap());
+
+        // Act
+        let first_lines = receive(&lines, 1);
+        append_bytes(&path, b"c\x00,\x00d\x00\n\x00");
+        let new_lines = receive(&lines, 1);
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        // Assert
+        assert_eq!(vec!["b"], first_lines);
+        assert_eq!(vec!["d"], new_lines);
+    }
+
+    #[test]
+    fn test_09_encoding() {
+        // Arrange
+        let path = format!("{}/follow_latin1.log", env!("CARGO_TARGET_TMPDIR"));
+        fs::write(&path, b"caf\xe9,1\n").unwrap();
+        let mut child = spawn_sbcut(vec!["-F", "-d", ",", "-f", "0", "--encoding", "latin1", &path]);
+        let lines = read_lines(child.stdout.take().unwrap());
+
+        // Act
+        let first_lines = receive(&lines, 1);
+        append_bytes(&path, b"cr\xe8me,2\n");
+        let new_lines = receive(&lines, 1);
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        // Assert
+        assert_eq!(vec!["café"], first_lines);
+        assert_eq!(vec!["crème"], new_lines);
+    }
+
+    #[test]
+    fn test_10_compressed_file() {
+        // Arrange
+        let path = format!("{}/follow_compressed.log", env!("CARGO_TARGET_TMPDIR"));
+        fs::write(&path, b"\x1f\x8b\x08\x00").unwrap();
+        let sbcut_arguments = vec!["-F", "-d", ",", "-f", "0", &path];
+
+        // Act
+        let output = Command::new("./target/debug/sbcut")
+            .args(sbcut_arguments)
+            .output()
+            .unwrap();
+
+        // Assert
+        assert_eq!(Some(1), output.status.code());
+        assert_eq!(
+            format!(
+                "sbcut: Can't follow the compressed file {}, use --decompress=none to read it as it is\n",
+                path
+            ),
+            String::from_utf8(output.stderr).unwrap()
+        );
+    }
+
+    fn spawn_sbcut(arguments: Vec<&str>) -> Child {
+        println!("sbcut: {}", arguments.join(" "));
+
+        Command::new("./target/debug/sbcut")
+            .args(arguments)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap()
+    }
+
+    fn read_lines(stdout: ChildStdout) -> Receiver<String> {
+        read_records(stdout, b'\n')
+    }
+
+    fn read_records(stdout: ChildStdout, separator: u8) -> Receiver<String> {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for record in BufReader::new(stdout).split(separator) {
+                if sender.send(String::from_utf8(record.unwrap()).unwrap()).is_err() {
+                    break;
+                }
+            }
+        });
+        receiver
+    }
+
+    fn receive(lines: &Receiver<String>, count: usize) -> Vec<String> {
+        (0..count)
+            .map(|_| lines.recv_timeout(TIMEOUT).unwrap())
+            .collect()
+    }
+
+    fn append(path: &str, content: &str) {
+        append_bytes(path, content.as_bytes());
+    }
+
+    fn append_bytes(path: &str, content: &[u8]) {
+        let mut file = OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(content).unwrap();
+    }
+}